@@ -9,39 +9,210 @@ mod models;
 mod network;
 mod npm;
 mod parser;
+mod publish_date_cache;
 mod scanner;
 mod ui;
 
+use async_lock::Mutex;
 use clap::Parser;
-use network::download_list_of_affected_packages;
-use npm::is_npm_installed;
-use parser::parse_npm_json;
-use scanner::{check_possible_vulnerable_packages, check_vulnerable_packages};
-use std::process;
-use ui::cli::{Args, resolve_lock_file_path};
+use models::package::PackageVulnerableRecord;
+use network::{download_list_of_affected_packages, AdvisorySource};
+use parser::parse_lock_file;
+use publish_date_cache::{PublishDateCache, PublishDateCacheOptions};
+use scanner::{
+    check_possible_vulnerable_packages, check_tampered_integrity, check_vulnerable_packages,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+    time::Duration,
+};
+use ui::cli::{find_all_lock_files, resolve_lock_file_path, Args};
+use ui::output::{build_scan_report, print_report};
 
 /// Main entry point for the Shai Hulud V2 vulnerability checker.
 ///
 /// This function orchestrates the vulnerability scanning process:
 /// 1. Parses CLI arguments
-/// 2. Verifies NPM is installed
-/// 3. Determines which package-lock.json file to scan
-/// 4. Parses the package-lock.json file
-/// 5. Downloads the list of known affected packages
-/// 6. Checks for known vulnerabilities
-/// 7. Checks for possible vulnerabilities based on publish dates
-/// 8. Reports all findings to the user
+/// 2. Determines which lock file(s) to scan (a single file, or every lock
+///    file under the current directory when `--recursive` is passed)
+/// 3. Downloads the list of known affected packages
+/// 4. Scans each lock file and reports findings in the requested `--format`
+///    (package metadata is fetched directly from the npm registry, falling
+///    back to the `npm` CLI only if that request fails and npm is installed)
+/// 5. Exits nonzero if any lock file's report met the `--fail-on` severity
+///    threshold (by default, any confirmed vulnerability), so CI pipelines
+///    can use this as a build gate
 fn main() {
     let args = Args::parse();
 
-    if !is_npm_installed() {
-        eprintln!("NPM is not installed or not found in PATH. Please install NPM to proceed.");
+    let advisory_source = AdvisorySource {
+        url: &args.advisory_url,
+        advisory_file: args.advisory_file.as_deref(),
+        offline: args.offline,
+        max_age: Duration::from_secs(args.max_age),
+    };
+    let affected_packages = download_list_of_affected_packages(&advisory_source);
+    println!(
+        "⏬ List of affected packages Downloaded! \n\t🔎 Found {} vulnerable 🦠 packages",
+        affected_packages.len()
+    );
+
+    let cache_options = PublishDateCacheOptions {
+        enabled: !args.no_cache,
+        refresh: args.refresh_cache,
+        max_age: Duration::from_secs(args.cache_max_age),
+    };
+    let publish_date_cache = Arc::new(Mutex::new(PublishDateCache::load(&cache_options)));
+
+    let has_confirmed_vulnerability = if args.recursive {
+        let scan_root = args
+            .scan_dir
+            .clone()
+            .unwrap_or_else(|| env::current_dir().expect("Failed to read current directory"));
+        let lock_file_paths = find_all_lock_files(&scan_root);
+
+        if lock_file_paths.is_empty() {
+            eprintln!(
+                "Error: No npm/yarn/pnpm lock file found under '{}'.",
+                scan_root.display()
+            );
+            process::exit(1);
+        }
+
+        println!(
+            "🔍 Recursive scan found {} lock file(s) under '{}'",
+            lock_file_paths.len(),
+            scan_root.display()
+        );
+
+        smol::block_on(scan_workspace(
+            &lock_file_paths,
+            &args,
+            &affected_packages,
+            &publish_date_cache,
+            &cache_options,
+        ))
+    } else {
+        let lock_file_path = resolve_lock_file_path(&args);
+        scan_lock_file(
+            &lock_file_path,
+            &args,
+            &affected_packages,
+            &publish_date_cache,
+            &cache_options,
+        )
+        .0
+    };
+
+    Arc::try_unwrap(publish_date_cache)
+        .expect("publish-date cache still shared after all scans completed")
+        .into_inner()
+        .save(&cache_options);
+
+    if has_confirmed_vulnerability {
         process::exit(1);
     }
+}
+
+/// Scans every lock file in `lock_file_paths` concurrently, bounded by
+/// `args.threads_num` the same way the per-package scans within a single
+/// lock file are, then prints a workspace-wide rollup of every unique
+/// vulnerable package found across the tree.
+///
+/// Each lock file's own scan is still blocking (it runs `npm view`/registry
+/// lookups of its own), so it's offloaded to `smol`'s blocking thread pool
+/// via `smol::unblock` rather than run directly on the async executor.
+///
+/// # Returns
+/// Whether any lock file's report met the `--fail-on` severity threshold.
+async fn scan_workspace(
+    lock_file_paths: &[PathBuf],
+    args: &Args,
+    affected_packages: &HashMap<String, PackageVulnerableRecord>,
+    publish_date_cache: &Arc<Mutex<PublishDateCache>>,
+    cache_options: &PublishDateCacheOptions,
+) -> bool {
+    let semaphore = Arc::new(async_lock::Semaphore::new(args.threads_num.max(1)));
+    let args = Arc::new(args.clone());
+    let affected_packages = Arc::new(affected_packages.clone());
+    let cache_options = Arc::new(*cache_options);
+
+    let mut tasks = Vec::new();
+    for lock_file_path in lock_file_paths {
+        let lock_file_path = lock_file_path.clone();
+        let args = Arc::clone(&args);
+        let affected_packages = Arc::clone(&affected_packages);
+        let semaphore = Arc::clone(&semaphore);
+        let publish_date_cache = Arc::clone(publish_date_cache);
+        let cache_options = Arc::clone(&cache_options);
+
+        let task = smol::spawn(async move {
+            let _permit = semaphore.acquire().await;
+            println!(
+                "\n========================================\n📂 Workspace: {}",
+                lock_file_path.display()
+            );
+            smol::unblock(move || {
+                scan_lock_file(
+                    &lock_file_path,
+                    &args,
+                    &affected_packages,
+                    &publish_date_cache,
+                    &cache_options,
+                )
+            })
+            .await
+        });
+        tasks.push(task);
+    }
+
+    let mut any_confirmed = false;
+    let mut unique_vulnerable_packages: HashSet<String> = HashSet::new();
+    for task in tasks {
+        let (confirmed, vulnerable_package_names) = task.await;
+        any_confirmed |= confirmed;
+        unique_vulnerable_packages.extend(vulnerable_package_names);
+    }
+
+    println!("\n========================================");
+    if unique_vulnerable_packages.is_empty() {
+        println!(
+            "✅ Workspace rollup: no unique vulnerable packages found across {} lock file(s)",
+            lock_file_paths.len()
+        );
+    } else {
+        let mut names: Vec<_> = unique_vulnerable_packages.into_iter().collect();
+        names.sort();
+        println!(
+            "❗ Workspace rollup: {} unique vulnerable package(s) found across {} lock file(s)",
+            names.len(),
+            lock_file_paths.len()
+        );
+        for name in names {
+            println!("\t- {}", name);
+        }
+    }
+
+    any_confirmed
+}
 
-    // Resolve lock file path from CLI arguments or auto-discovery
-    let lock_file_path = resolve_lock_file_path(&args);
-    let npm_packages = parse_npm_json(&lock_file_path);
+/// Scans a single lock file for known and possible vulnerabilities, prints a
+/// report in the requested `--format`, and returns whether the report meets
+/// the `--fail-on` severity threshold, along with the names of every package
+/// confirmed vulnerable (known-CSV match or tampered integrity) for the
+/// workspace-wide rollup.
+fn scan_lock_file(
+    lock_file_path: &Path,
+    args: &Args,
+    affected_packages: &HashMap<String, PackageVulnerableRecord>,
+    publish_date_cache: &Arc<Mutex<PublishDateCache>>,
+    cache_options: &PublishDateCacheOptions,
+) -> (bool, Vec<String>) {
+    let npm_packages = parse_lock_file(lock_file_path);
 
     println!(
         "🔧 Using {} concurrent threads for npm view commands",
@@ -52,66 +223,56 @@ fn main() {
         npm_packages.packages.len()
     );
 
-    let affected_packages = download_list_of_affected_packages();
-
-    println!(
-        "⏬ List of affected packages Downloaded! \n\t🔎 Found {} vulnerable 🦠 packages",
-        affected_packages.len()
-    );
+    // Third check: installed tarballs vs. the lock file's recorded integrity hashes
+    let tampered_integrity_findings = smol::block_on(check_tampered_integrity(
+        &npm_packages,
+        args.threads_num,
+        args.offline,
+    ));
 
     // First check: known vulnerabilities
     let (npm_packages, vulnerable_packages) =
-        check_vulnerable_packages(&affected_packages, npm_packages);
+        check_vulnerable_packages(affected_packages, npm_packages);
 
     // Second check: possible vulnerabilities based on publish date
-    let (_remaining_packages, possibly_vulnerable_packages) = smol::block_on(
-        check_possible_vulnerable_packages(npm_packages, args.threads_num),
-    );
+    let (remaining_packages, possibly_vulnerable_packages) =
+        smol::block_on(check_possible_vulnerable_packages(
+            npm_packages,
+            args.threads_num,
+            publish_date_cache,
+            *cache_options,
+            args.offline,
+        ));
 
-    let vulnerable_packages_count = vulnerable_packages.packages.len();
-    let possibly_vulnerable_packages_count = possibly_vulnerable_packages.packages.len();
-    let skipped_packages: Vec<_> = possibly_vulnerable_packages
+    let mut report = build_scan_report(
+        lock_file_path,
+        affected_packages,
+        &vulnerable_packages,
+        &possibly_vulnerable_packages,
+        &tampered_integrity_findings,
+    );
+    // Packages whose publish-date lookup was skipped stay in the remaining
+    // set rather than the possibly-vulnerable one, so collect them from there.
+    report.skipped_packages = remaining_packages
         .packages
         .iter()
-        .filter(|(_, v)| v.skipped_scan)
+        .filter(|(_, info)| info.skipped_scan)
+        .map(|(name, _)| name.clone())
         .collect();
-    let skipped_packages_count = skipped_packages.len();
-
-    println!("\n🔚 Scan completed!");
-    if vulnerable_packages_count == 0 {
-        println!("✅ No vulnerable packages found!");
-    } else {
-        println!(
-            "❗ Total vulnerable packages found: {}",
-            vulnerable_packages_count
-        );
+    report.advisory_source = match &args.advisory_file {
+        Some(path) => path.display().to_string(),
+        None => args.advisory_url.clone(),
+    };
 
-        for vuln_package in vulnerable_packages.packages.keys() {
-            println!("\t- {}", vuln_package);
-        }
-    }
-
-    if possibly_vulnerable_packages_count == 0 {
-        println!("✅ No possibly vulnerable packages found!");
-    } else {
-        println!(
-            "⚠️  Total possibly vulnerable packages found: {}",
-            possibly_vulnerable_packages_count
-        );
+    print_report(&report, args.format);
 
-        for possible_vuln_package in possibly_vulnerable_packages.packages.keys() {
-            println!("\t- {}", possible_vuln_package);
-        }
-    }
-
-    if skipped_packages_count > 0 {
-        println!(
-            "⚠️  Total packages skipped during possible vulnerability check: {}",
-            skipped_packages_count
-        );
+    let exceeds_threshold = report.exceeds_threshold(args.fail_on);
+    let vulnerable_package_names = report
+        .findings
+        .iter()
+        .filter(|f| f.is_confirmed())
+        .map(|f| f.package.clone())
+        .collect();
 
-        for (skipped_package_name, _) in skipped_packages {
-            println!("\t- {}", skipped_package_name);
-        }
-    }
+    (exceeds_threshold, vulnerable_package_names)
 }
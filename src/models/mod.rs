@@ -5,3 +5,4 @@
 //! and vulnerability CSV files.
 pub mod package;
 pub mod serde_helpers;
+pub mod version_matcher;
@@ -3,8 +3,9 @@
 //! This module defines the core data structures used throughout the application
 //! for representing package information, vulnerability data, and NPM metadata.
 
-use super::serde_helpers::{clean_version_name, split_versions, to_vec};
-use serde::Deserialize;
+use super::serde_helpers::{clean_version_name, flatten_v1_dependencies, split_hash_list};
+use super::version_matcher::{parse_version_matcher, VersionMatcher};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 
 /// Represents metadata retrieved from `npm view` for a specific package.
@@ -17,20 +18,71 @@ pub struct PackageView {
     pub time: HashMap<String, String>,
 }
 
+/// The npm registry's distribution metadata for one exact package version:
+/// the modern SRI `integrity` hash and/or the legacy hex `shasum` digest.
+///
+/// Used as a fallback source of truth for integrity verification when a
+/// lock file entry has no `resolved` tarball URL to re-download and hash
+/// directly.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct DistInfo {
+    /// SRI hash (e.g. `"sha512-..."`), present on all modern registry entries.
+    pub integrity: Option<String>,
+    /// Legacy hex-encoded SHA-1 digest, kept for registries/entries that
+    /// predate the `integrity` field.
+    pub shasum: Option<String>,
+}
+
 /// Represents the complete set of packages from a package-lock.json file.
 ///
 /// This structure is deserialized from package-lock.json with custom processing
 /// to extract clean package names and normalize the data structure.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default)]
 pub struct NpmLockPackages {
     /// Map of package names to their installation information
     ///
     /// The keys are clean package names (e.g., "express" or "@babel/core")
     /// extracted from the full node_modules paths in package-lock.json
-    #[serde(deserialize_with = "clean_version_name")]
     pub packages: HashMap<String, PackageInfo>,
 }
 
+/// Raw shape of a package-lock.json file before we know which `lockfileVersion`
+/// produced it.
+///
+/// lockfileVersion 2/3 describe installed packages via the flat `packages` map
+/// (keyed by `node_modules/...` paths), while lockfileVersion 1 only has the
+/// legacy, recursively nested `dependencies` tree. Both are present on the raw
+/// struct (each defaulting to empty) so a single `serde_json` pass can read
+/// either shape.
+#[derive(Debug, Deserialize, Default)]
+struct RawNpmLockFile {
+    #[serde(rename = "lockfileVersion", default)]
+    lockfile_version: Option<u64>,
+
+    #[serde(default, deserialize_with = "clean_version_name")]
+    packages: HashMap<String, PackageInfo>,
+
+    #[serde(default)]
+    dependencies: HashMap<String, super::serde_helpers::V1DependencyEntry>,
+}
+
+impl<'de> Deserialize<'de> for NpmLockPackages {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = RawNpmLockFile::deserialize(deserializer)?;
+
+        let packages = if raw.lockfile_version == Some(1) {
+            flatten_v1_dependencies(&raw.dependencies)
+        } else {
+            raw.packages
+        };
+
+        Ok(NpmLockPackages { packages })
+    }
+}
+
 impl NpmLockPackages {
     /// Creates a new empty `NpmLockPackages` instance.
     ///
@@ -48,39 +100,88 @@ impl NpmLockPackages {
     }
 }
 
+/// One lock-file-recorded resolution of a package: the version plus
+/// whatever integrity hash/tarball URL metadata was recorded alongside it
+/// for that exact occurrence.
+///
+/// Kept together as a single unit (rather than as separate, independently
+/// deduplicated vectors on `PackageInfo`) so merging duplicate lock file
+/// entries for the same package can never desync a version from the wrong
+/// integrity hash — which would silently defeat tampered-tarball detection
+/// whenever a package is hoisted to more than one place in the tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageResolution {
+    /// The installed version number for this resolution.
+    pub version: String,
+    /// Subresource Integrity (SRI) hash recorded for this resolution (e.g.
+    /// `"sha512-..."`), if any.
+    pub integrity: Option<String>,
+    /// Tarball URL recorded for this resolution, if any. Used to re-download
+    /// and re-hash the exact installed tarball when verifying integrity.
+    pub resolved: Option<String>,
+}
+
 /// Information about an installed package.
 ///
 /// This structure represents a single package entry from package-lock.json,
-/// including its installed version(s) and scan status.
-#[derive(Debug, Deserialize, Default, Clone)]
+/// including its installed resolution(s) and scan status.
+#[derive(Debug, Default, Clone)]
 pub struct PackageInfo {
-    /// List of installed version numbers for this package
-    ///
-    /// Multiple versions can exist if the package appears at different
-    /// locations in the dependency tree
-    #[serde(deserialize_with = "to_vec")]
-    pub version: Vec<String>,
+    /// Every resolution of this package found in the lock file. Multiple
+    /// resolutions can exist if the package appears at different locations
+    /// in the dependency tree.
+    pub resolutions: Vec<PackageResolution>,
 
     /// Whether this package was skipped during vulnerability scanning
     ///
     /// Set to true if NPM metadata could not be retrieved for this package
-    #[serde(default)]
     pub skipped_scan: bool,
 }
 
+impl PackageInfo {
+    /// Every installed version number for this package, in the same order
+    /// as `resolutions`.
+    pub fn versions(&self) -> impl Iterator<Item = &str> {
+        self.resolutions.iter().map(|r| r.version.as_str())
+    }
+
+    /// Every recorded integrity hash for this package, in the same order
+    /// as `resolutions`. Resolutions without one are skipped.
+    pub fn integrities(&self) -> impl Iterator<Item = &str> {
+        self.resolutions
+            .iter()
+            .filter_map(|r| r.integrity.as_deref())
+    }
+}
+
 /// A record from the CSV file of known vulnerable packages.
 ///
 /// This structure represents a single row in the Shai Hulud V2 vulnerability
 /// CSV, containing a package name and its affected versions.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct PackageVulnerableRecord {
     /// The name of the vulnerable package
     #[serde(rename(deserialize = "Package"))]
     pub package: String,
 
-    /// List of version numbers known to be vulnerable
+    /// The range of versions known to be vulnerable, parsed from npm-style
+    /// range syntax (e.g. "=1.0.0||=1.0.1", ">=2.0.0 <2.5.0", "^4.17.0").
+    #[serde(
+        rename(deserialize = "Version"),
+        deserialize_with = "parse_version_matcher"
+    )]
+    pub version: VersionMatcher,
+
+    /// Known-malicious Subresource Integrity (SRI) hashes for the
+    /// republished tarball(s), if the advisory source records any.
     ///
-    /// Versions are split from CSV format (e.g., "1.0.0||1.0.1" becomes ["1.0.0", "1.0.1"])
-    #[serde(rename(deserialize = "Version"), deserialize_with = "split_versions")]
-    pub version: Vec<String>,
+    /// This lets a package be flagged even when an attacker rewrote the
+    /// lockfile's version string, since the tarball's integrity hash is the
+    /// one thing that can't be faked without changing the content.
+    #[serde(
+        rename(deserialize = "Integrity"),
+        default,
+        deserialize_with = "split_hash_list"
+    )]
+    pub integrity: Vec<String>,
 }
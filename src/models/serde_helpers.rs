@@ -3,38 +3,62 @@
 //! This module provides specialized deserializers that transform data during
 //! JSON/CSV parsing to create clean, normalized data structures.
 
-use super::package::PackageInfo;
+use super::package::{PackageInfo, PackageResolution};
 use regex::Regex;
 use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 
-/// Deserializes a comma/pipe-separated version string into a vector.
+/// Deserializes a "||"-separated list of Subresource Integrity hashes into a vector.
 ///
-/// This function parses version strings from the CSV format (e.g., "1.0.0||1.0.1")
-/// into a vector of individual version strings (e.g., ["1.0.0", "1.0.1"]).
-/// It also removes "=" prefix characters that may appear in the CSV.
-///
-/// # Format
-/// - Versions are separated by "||"
-/// - Leading "=" characters are stripped from each version
-/// - Whitespace is trimmed
+/// Unlike version strings, integrity hashes are base64 and commonly end in
+/// "=" padding, so (unlike the old version-string parsing this replaces)
+/// this does *not* strip "=" characters — only splits on "||", trims
+/// surrounding whitespace, and drops empty entries (so an empty field
+/// yields no hashes).
 ///
 /// # Examples
 /// ```text
-/// Input:  "=1.0.0||=1.0.1"
-/// Output: vec!["1.0.0", "1.0.1"]
+/// Input:  "sha512-abc==||sha512-def=="
+/// Output: vec!["sha512-abc==", "sha512-def=="]
 /// ```
-pub fn split_versions<'a, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+pub fn split_hash_list<'a, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
     D: Deserializer<'a>,
 {
-    let str = String::deserialize(deserializer).expect("Failed to deserialize versions string");
-    let versions: Vec<String> = str
+    let str = String::deserialize(deserializer).expect("Failed to deserialize hash list string");
+    let hashes: Vec<String> = str
         .split("||")
-        .map(|s| String::from(s.replace("=", "").trim()))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
         .collect();
 
-    Ok(versions)
+    Ok(hashes)
+}
+
+/// Raw shape of one entry in package-lock.json's lockfileVersion 2/3
+/// `packages` map: a single resolution's version plus whatever integrity
+/// hash/tarball URL metadata was recorded alongside it.
+#[derive(Debug, Deserialize)]
+struct RawPackageEntry {
+    version: String,
+    #[serde(default)]
+    integrity: Option<String>,
+    #[serde(default)]
+    resolved: Option<String>,
+}
+
+/// Merges `resolution` into `package_info`'s resolutions, skipping it only
+/// if an identical `(version, integrity, resolved)` triple is already
+/// present. This is what keeps `PackageInfo`'s resolutions genuinely
+/// aligned: deduplicating the whole resolution as one unit, rather than
+/// deduplicating `version`/`integrity`/`resolved` independently, which
+/// could desync a version from the wrong integrity hash when the same
+/// version shows up twice with different metadata (exactly the signal a
+/// tarball-swap detector depends on).
+fn merge_resolution(package_info: &mut PackageInfo, resolution: PackageResolution) {
+    if !package_info.resolutions.contains(&resolution) {
+        package_info.resolutions.push(resolution);
+    }
 }
 
 /// Deserializes and cleans package names from package-lock.json node_modules paths.
@@ -46,9 +70,14 @@ where
 /// # Transformation
 /// - Extracts package name from paths like "node_modules/package-name"
 /// - Handles scoped packages like "@scope/package-name"
-/// - Merges duplicate packages with different versions
-/// - Deduplicates version numbers within each package
+/// - Merges duplicate packages, keeping each resolution's version/integrity/
+///   resolved together so a repeated entry with differing metadata can't
+///   desync the two
 /// - Skips empty keys
+/// - Skips keys that aren't a `node_modules/...` path, e.g. the root
+///   package entry (key `""`, already covered above) or an npm workspace's
+///   local member packages (key `"packages/foo"`), which aren't installed
+///   dependencies pulled from the registry and have nothing to scan
 ///
 /// # Examples
 /// ```text
@@ -58,69 +87,206 @@ where
 /// Input key:  "node_modules/@babel/core"
 /// Output key: "@babel/core"
 /// ```
-///
-/// # Panics
-/// Panics if the regex pattern fails to match a valid node_modules path.
 pub fn clean_version_name<'a, D>(deserializer: D) -> Result<HashMap<String, PackageInfo>, D::Error>
 where
     D: Deserializer<'a>,
 {
-    let mut hash_map: HashMap<String, PackageInfo> =
+    let hash_map: HashMap<String, RawPackageEntry> =
         HashMap::deserialize(deserializer).expect("Failed to deserialize version name string");
 
     let mut corrected_map: HashMap<String, PackageInfo> = HashMap::new();
     let re = Regex::new(r".*node_modules/(@{0,1}.+)$").expect("Invalid regex pattern");
-    let map_keys: Vec<String> = hash_map.keys().map(|k| k.to_string()).collect();
-    for k in map_keys {
+    for (k, value) in hash_map {
         if k.is_empty() {
             continue;
         }
 
-        let clean_key = re
+        let Some(clean_key) = re
             .captures(&k)
-            .expect("No captures found")
-            .get(1)
-            .expect("No match found")
-            .as_str()
-            .to_string();
-
-        let mut value = hash_map.remove(&k).expect("Failed to remove the entry");
-        if corrected_map.contains_key(&clean_key) {
-            let value_version = value.version.pop().unwrap();
-            let corrected_versions = &corrected_map.get(&clean_key).unwrap().version;
-            if corrected_versions.contains(&value_version) {
-                continue;
-            }
-
-            corrected_map
-                .get_mut(&clean_key)
-                .unwrap()
-                .version
-                .push(value_version);
+            .and_then(|captures| captures.get(1))
+            .map(|m| m.as_str().to_string())
+        else {
+            // Not a `node_modules/...` path (e.g. an npm workspace's local
+            // member package) — nothing to scan, so leave it out of the map.
             continue;
-        }
-        corrected_map.insert(clean_key, value);
+        };
+
+        let package_info = corrected_map.entry(clean_key).or_default();
+        merge_resolution(
+            package_info,
+            PackageResolution {
+                version: value.version,
+                integrity: value.integrity,
+                resolved: value.resolved,
+            },
+        );
     }
 
     Ok(corrected_map)
 }
 
-/// Deserializes a single string value into a vector containing that string.
+/// A single node of the legacy lockfileVersion 1 `dependencies` tree.
 ///
-/// This helper wraps a string field into a vector, allowing the application to
-/// uniformly handle package versions as vectors even when package-lock.json
-/// only contains a single version string.
+/// Unlike the flat `packages` map used by lockfileVersion 2/3, version 1
+/// nests every dependency's own transitive dependencies under it, so the
+/// same package/version pair can legitimately show up at many depths.
+#[derive(Debug, Deserialize)]
+pub struct V1DependencyEntry {
+    pub version: String,
+    #[serde(default)]
+    pub integrity: Option<String>,
+    #[serde(default)]
+    pub resolved: Option<String>,
+    #[serde(default)]
+    pub dependencies: HashMap<String, V1DependencyEntry>,
+}
+
+/// How many `dependencies` levels deep `flatten_v1_dependencies` will
+/// recurse before giving up on the remaining subtree. A real v1 lockfile
+/// never nests anywhere near this deep; this only exists to bound recursion
+/// on a pathologically deep, adversarially-crafted `dependencies` tree.
+const MAX_V1_DEPENDENCY_DEPTH: usize = 200;
+
+/// Flattens a lockfileVersion 1 `dependencies` tree into the same
+/// `name -> PackageInfo` shape the rest of the pipeline expects from v2/v3.
 ///
-/// # Examples
-/// ```text
-/// Input:  "1.2.3"
-/// Output: vec!["1.2.3"]
-/// ```
-pub fn to_vec<'a, D>(deserializer: D) -> Result<Vec<String>, D::Error>
-where
-    D: Deserializer<'a>,
-{
-    let s = String::deserialize(deserializer).expect("Failed to deserialize version string");
+/// The tree is walked depth-first, merging every occurrence of a given
+/// package name into a single `PackageInfo`. v1 lockfiles are known to list
+/// the same package/version more than once (hoisted to multiple places in
+/// the tree) with differing metadata (e.g. one copy recording an
+/// `integrity` hash the other lacks), so every occurrence is merged rather
+/// than just the first one seen for a given `(name, version)` — keeping
+/// each resolution's version/integrity/resolved together so that merge
+/// can't desync them. The tree comes from a single `serde_json` parse,
+/// which can't produce shared references, so there's no cycle to guard
+/// against — but recursion is still capped at `MAX_V1_DEPENDENCY_DEPTH` so
+/// a pathologically deep, adversarially-crafted `dependencies` tree can't
+/// blow the stack.
+pub fn flatten_v1_dependencies(
+    dependencies: &HashMap<String, V1DependencyEntry>,
+) -> HashMap<String, PackageInfo> {
+    let mut flattened: HashMap<String, PackageInfo> = HashMap::new();
+    flatten_v1_dependencies_into(dependencies, &mut flattened, 0);
+    flattened
+}
+
+fn flatten_v1_dependencies_into(
+    dependencies: &HashMap<String, V1DependencyEntry>,
+    flattened: &mut HashMap<String, PackageInfo>,
+    depth: usize,
+) {
+    if depth >= MAX_V1_DEPENDENCY_DEPTH {
+        return;
+    }
+
+    for (name, entry) in dependencies {
+        let package_info = flattened.entry(name.clone()).or_default();
+        merge_resolution(
+            package_info,
+            PackageResolution {
+                version: entry.version.clone(),
+                integrity: entry.integrity.clone(),
+                resolved: entry.resolved.clone(),
+            },
+        );
+
+        flatten_v1_dependencies_into(&entry.dependencies, flattened, depth + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clean_version_name, flatten_v1_dependencies, V1DependencyEntry, MAX_V1_DEPENDENCY_DEPTH};
+    use crate::models::package::PackageInfo;
+    use std::collections::HashMap;
+
+    #[derive(serde::Deserialize)]
+    struct Wrapper(#[serde(deserialize_with = "clean_version_name")] HashMap<String, PackageInfo>);
 
-    Ok(vec![s])
+    #[test]
+    fn workspace_member_keys_are_skipped_instead_of_panicking() {
+        let json = r#"{
+            "": { "version": "1.0.0" },
+            "packages/foo": { "version": "1.0.0" },
+            "node_modules/express": { "version": "4.18.0" }
+        }"#;
+
+        let Wrapper(packages) = serde_json::from_str(json).expect("should not panic");
+
+        assert_eq!(packages.len(), 1);
+        assert!(packages.contains_key("express"));
+        assert!(!packages.contains_key("packages/foo"));
+    }
+
+    #[test]
+    fn duplicate_node_modules_paths_merge_versions() {
+        let json = r#"{
+            "node_modules/foo/node_modules/express": { "version": "4.17.0" },
+            "node_modules/express": { "version": "4.18.0" }
+        }"#;
+
+        let Wrapper(packages) = serde_json::from_str(json).expect("should not panic");
+
+        let express = packages.get("express").expect("express should be present");
+        let versions: Vec<&str> = express.versions().collect();
+        assert_eq!(versions.len(), 2);
+        assert!(versions.contains(&"4.17.0"));
+        assert!(versions.contains(&"4.18.0"));
+    }
+
+    #[test]
+    fn duplicate_version_with_differing_integrity_keeps_both_aligned() {
+        let json = r#"{
+            "node_modules/foo/node_modules/left-pad": {
+                "version": "1.0.0",
+                "integrity": "sha512-good=="
+            },
+            "node_modules/left-pad": {
+                "version": "1.0.0",
+                "integrity": "sha512-tampered=="
+            }
+        }"#;
+
+        let Wrapper(packages) = serde_json::from_str(json).expect("should not panic");
+
+        let left_pad = packages
+            .get("left-pad")
+            .expect("left-pad should be present");
+        assert_eq!(left_pad.resolutions.len(), 2);
+
+        let integrities: Vec<&str> = left_pad.integrities().collect();
+        assert!(integrities.contains(&"sha512-good=="));
+        assert!(integrities.contains(&"sha512-tampered=="));
+
+        // Each resolution still pairs its own version with its own integrity.
+        for resolution in &left_pad.resolutions {
+            assert_eq!(resolution.version, "1.0.0");
+        }
+    }
+
+    #[test]
+    fn deeply_nested_v1_tree_does_not_blow_the_stack() {
+        let depth = MAX_V1_DEPENDENCY_DEPTH * 2;
+
+        let mut dependencies = HashMap::new();
+        for level in (0..depth).rev() {
+            let mut child = HashMap::new();
+            std::mem::swap(&mut child, &mut dependencies);
+
+            let mut entry = HashMap::new();
+            entry.insert(
+                format!("pkg-{level}"),
+                V1DependencyEntry {
+                    version: "1.0.0".to_string(),
+                    integrity: None,
+                    resolved: None,
+                    dependencies: child,
+                },
+            );
+            dependencies = entry;
+        }
+
+        let flattened = flatten_v1_dependencies(&dependencies);
+        assert_eq!(flattened.len(), MAX_V1_DEPENDENCY_DEPTH);
+    }
 }
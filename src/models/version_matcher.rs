@@ -0,0 +1,267 @@
+//! npm-style version range matching for vulnerability advisories.
+//!
+//! Advisories describe affected versions using npm's own semver range
+//! syntax: an OR (`||`) of alternatives, each itself an AND (whitespace
+//! separated) of comparator terms written with `^`, `~`, a bare exact
+//! version, or an explicit `=`/`>=`/`<=`/`>`/`<` operator. This mirrors
+//! deno's `NpmVersionMatcher`: each alternative is parsed into a list of
+//! `Comparator`s, and an installed version matches the advisory if it
+//! satisfies every comparator of at least one alternative.
+//!
+//! This is deliberately a hand-rolled parser rather than a pass-through to
+//! `semver::VersionReq`: npm and Cargo disagree on what a bare version
+//! means (npm: exact match, Cargo: implicit caret), and npm advisories are
+//! written in npm's dialect.
+
+use semver::Version;
+use serde::{Deserialize, Deserializer};
+
+/// An npm-style comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Exact,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Tilde,
+    Caret,
+}
+
+/// A single comparator term, e.g. `^4.17.0` or `>=1.0.0`.
+#[derive(Debug, Clone)]
+struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+impl Comparator {
+    fn matches(&self, installed: &Version) -> bool {
+        match self.op {
+            Op::Exact => installed == &self.version,
+            Op::Gt => installed > &self.version,
+            Op::Gte => installed >= &self.version,
+            Op::Lt => installed < &self.version,
+            Op::Lte => installed <= &self.version,
+            Op::Tilde => installed >= &self.version && installed < &tilde_upper_bound(&self.version),
+            Op::Caret => installed >= &self.version && installed < &caret_upper_bound(&self.version),
+        }
+    }
+}
+
+/// The `~1.2.3` upper bound: the next minor version.
+fn tilde_upper_bound(version: &Version) -> Version {
+    Version::new(version.major, version.minor + 1, 0)
+}
+
+/// The `^1.2.3` upper bound, following npm's usual 0.x caret rules: the
+/// next version that changes the left-most nonzero component.
+fn caret_upper_bound(version: &Version) -> Version {
+    if version.major > 0 {
+        Version::new(version.major + 1, 0, 0)
+    } else if version.minor > 0 {
+        Version::new(0, version.minor + 1, 0)
+    } else {
+        Version::new(0, 0, version.patch + 1)
+    }
+}
+
+/// Parses a dotted version that may omit trailing components (`"1.2"`,
+/// `"4"`), defaulting missing minor/patch to `0`, same as npm does for
+/// partial range specifiers.
+fn parse_partial_version(text: &str) -> Option<Version> {
+    let (core, suffix) = match text.find(['-', '+']) {
+        Some(idx) => (&text[..idx], &text[idx..]),
+        None => (text, ""),
+    };
+
+    let mut parts = core.split('.');
+    let major = parts.next()?.parse::<u64>().ok()?;
+    let minor = parts
+        .next()
+        .map(str::parse::<u64>)
+        .transpose()
+        .ok()?
+        .unwrap_or(0);
+    let patch = parts
+        .next()
+        .map(str::parse::<u64>)
+        .transpose()
+        .ok()?
+        .unwrap_or(0);
+
+    Version::parse(&format!("{major}.{minor}.{patch}{suffix}")).ok()
+}
+
+/// Parses one AND-term (e.g. `^4.17.0`, `>=1.0.0`, `1.2.3`) into a `Comparator`.
+fn parse_comparator(term: &str) -> Option<Comparator> {
+    let term = term.trim();
+    if term.is_empty() {
+        return None;
+    }
+
+    let (op, rest) = if let Some(rest) = term.strip_prefix(">=") {
+        (Op::Gte, rest)
+    } else if let Some(rest) = term.strip_prefix("<=") {
+        (Op::Lte, rest)
+    } else if let Some(rest) = term.strip_prefix('>') {
+        (Op::Gt, rest)
+    } else if let Some(rest) = term.strip_prefix('<') {
+        (Op::Lt, rest)
+    } else if let Some(rest) = term.strip_prefix('^') {
+        (Op::Caret, rest)
+    } else if let Some(rest) = term.strip_prefix('~') {
+        (Op::Tilde, rest)
+    } else if let Some(rest) = term.strip_prefix('=') {
+        (Op::Exact, rest)
+    } else {
+        (Op::Exact, term)
+    };
+
+    let version = parse_partial_version(rest.trim())?;
+    Some(Comparator { op, version })
+}
+
+/// Whether every comparator in `terms` matches `installed`, honoring the
+/// usual semver rule that a prerelease version only matches a range when
+/// the range itself references that exact major.minor.patch with a
+/// prerelease tag of its own (otherwise `^1.0.0` would silently pull in
+/// every future prerelease).
+fn alternative_matches(terms: &[Comparator], installed: &Version) -> bool {
+    if terms.is_empty() || !terms.iter().all(|term| term.matches(installed)) {
+        return false;
+    }
+
+    if installed.pre.is_empty() {
+        return true;
+    }
+
+    terms.iter().any(|term| {
+        !term.version.pre.is_empty()
+            && term.version.major == installed.major
+            && term.version.minor == installed.minor
+            && term.version.patch == installed.patch
+    })
+}
+
+/// An npm-style version range, parsed from an advisory's `Version` field.
+///
+/// Built from OR-alternatives (`||`) of AND-terms (whitespace-separated
+/// comparators). Falls back to matching the installed version string
+/// against the original advisory text verbatim, so an advisory entry that
+/// isn't valid npm range syntax still matches an identical string instead
+/// of silently matching nothing.
+#[derive(Debug, Clone, Default)]
+pub struct VersionMatcher {
+    alternatives: Vec<Vec<Comparator>>,
+    raw: String,
+}
+
+impl VersionMatcher {
+    /// Parses an advisory version field (e.g. `"=1.0.0||=1.0.1"`,
+    /// `">=2.0.0 <2.5.0"`, `"^4.17.0"`) into a `VersionMatcher`.
+    pub fn parse(raw: &str) -> Self {
+        let alternatives = raw
+            .split("||")
+            .map(|alternative| {
+                alternative
+                    .split_whitespace()
+                    .filter_map(parse_comparator)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|terms| !terms.is_empty())
+            .collect();
+
+        VersionMatcher {
+            alternatives,
+            raw: raw.trim().to_string(),
+        }
+    }
+
+    /// Whether `installed_version` satisfies this range. An installed
+    /// version that isn't valid semver can't be evaluated against a parsed
+    /// range, so it falls back to comparing the raw strings rather than
+    /// panicking.
+    pub fn matches(&self, installed_version: &str) -> bool {
+        if let Ok(installed) = Version::parse(installed_version) {
+            if self
+                .alternatives
+                .iter()
+                .any(|alternative| alternative_matches(alternative, &installed))
+            {
+                return true;
+            }
+        }
+
+        installed_version == self.raw
+    }
+
+    /// The original, unparsed range string this matcher was built from, e.g.
+    /// `"=1.0.0||=1.0.1"`. Surfaced in reports so a finding can cite exactly
+    /// which advisory range matched.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+}
+
+/// Deserializes an advisory `Version` CSV field into a `VersionMatcher`.
+pub fn parse_version_matcher<'de, D>(deserializer: D) -> Result<VersionMatcher, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    Ok(VersionMatcher::parse(&raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VersionMatcher;
+
+    #[test]
+    fn exact_version_matches_only_that_version() {
+        let matcher = VersionMatcher::parse("=1.0.0||=1.0.1");
+        assert!(matcher.matches("1.0.0"));
+        assert!(matcher.matches("1.0.1"));
+        assert!(!matcher.matches("1.0.2"));
+        assert!(!matcher.matches("2.0.0"));
+    }
+
+    #[test]
+    fn range_matches_bounds_inclusive_exclusive() {
+        let matcher = VersionMatcher::parse(">=2.0.0 <2.5.0");
+        assert!(matcher.matches("2.0.0"));
+        assert!(matcher.matches("2.4.9"));
+        assert!(!matcher.matches("2.5.0"));
+        assert!(!matcher.matches("1.9.9"));
+    }
+
+    #[test]
+    fn caret_range_matches_same_major_only() {
+        let matcher = VersionMatcher::parse("^4.17.0");
+        assert!(matcher.matches("4.17.0"));
+        assert!(matcher.matches("4.99.0"));
+        assert!(!matcher.matches("5.0.0"));
+        assert!(!matcher.matches("4.16.9"));
+    }
+
+    #[test]
+    fn tilde_range_matches_same_minor_only() {
+        let matcher = VersionMatcher::parse("~1.2.3");
+        assert!(matcher.matches("1.2.3"));
+        assert!(matcher.matches("1.2.9"));
+        assert!(!matcher.matches("1.3.0"));
+    }
+
+    #[test]
+    fn non_semver_installed_version_falls_back_to_raw_string_match() {
+        let matcher = VersionMatcher::parse("git+https://example.com/pkg.git");
+        assert!(matcher.matches("git+https://example.com/pkg.git"));
+        assert!(!matcher.matches("1.0.0"));
+    }
+
+    #[test]
+    fn unrelated_package_version_does_not_match() {
+        let matcher = VersionMatcher::parse("=1.0.0");
+        assert!(!matcher.matches("2.0.0"));
+    }
+}
@@ -1,91 +1,235 @@
 //! Network operations for downloading vulnerability data.
 //!
 //! This module handles fetching the list of packages affected by the Shai Hulud V2
-//! supply chain attack from the official Wiz Security research repository.
+//! supply chain attack from the official Wiz Security research repository. The
+//! downloaded CSV is cached on disk so repeated runs don't need a network round
+//! trip every time, and so the tool keeps working offline or through a rate limit.
 
 use crate::models::package::PackageVulnerableRecord;
-use std::{collections::HashMap, process};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
 
 /// URL to the CSV file containing the list of packages affected by Shai Hulud V2
-const AFFECTED_PACKAGES_URL: &str = "https://github.com/wiz-sec-public/wiz-research-iocs/raw/refs/heads/main/reports/shai-hulud-2-packages.csv";
+pub(crate) const AFFECTED_PACKAGES_URL: &str = "https://github.com/wiz-sec-public/wiz-research-iocs/raw/refs/heads/main/reports/shai-hulud-2-packages.csv";
+
+/// Default maximum age, in seconds, of the on-disk advisory cache before a
+/// fresh download is attempted.
+pub const DEFAULT_MAX_AGE_SECS: u64 = 3600;
+
+/// Where the advisory CSV should come from and how stale a cached copy is
+/// allowed to be.
+pub struct AdvisorySource<'a> {
+    /// URL to download the CSV from. Ignored when `advisory_file` is set.
+    pub url: &'a str,
+    /// A local CSV file to read instead of downloading one, if provided.
+    pub advisory_file: Option<&'a Path>,
+    /// Use the on-disk cache without attempting a network request at all.
+    pub offline: bool,
+    /// Maximum age of the on-disk cache before a fresh download is attempted.
+    pub max_age: Duration,
+}
+
+/// The on-disk cache of the last successfully downloaded advisory CSV.
+#[derive(Debug, Serialize, Deserialize)]
+struct AdvisoryCache {
+    fetched_at: DateTime<Utc>,
+    csv: String,
+}
 
 /// Downloads and parses the list of packages affected by the Shai Hulud V2 attack.
 ///
-/// This function fetches a CSV file from the Wiz Security research repository containing
-/// package names and their vulnerable versions. The CSV is parsed and transformed into
-/// a HashMap for efficient lookup during vulnerability scanning.
+/// By default this fetches the CSV from `source.url`, caching it on disk so
+/// later runs within `source.max_age` reuse the cached copy instead of
+/// re-downloading. Pass `source.advisory_file` to read a local CSV (e.g. an
+/// internal IOC source) instead, or `source.offline` to use the cache
+/// without touching the network at all.
 ///
 /// # Returns
 /// A HashMap where:
 /// - Keys are package names (String)
-/// - Values are vectors of vulnerable version strings (`Vec<String>`)
+/// - Values are the full `PackageVulnerableRecord`, carrying both the
+///   vulnerable version list and any known-malicious integrity hashes
 ///
 /// # Panics
 /// This function will exit the process (via `process::exit(1)`) if:
-/// - The HTTP request fails
-/// - The HTTP response status is not 200
-/// - The response body cannot be decoded as text
-/// - The CSV parsing fails
+/// - `source.advisory_file` is set but can't be read
+/// - `source.offline` is set but no usable cache exists
+/// - The download fails and no usable cache exists to fall back to
+/// - The resulting CSV fails to parse
 ///
 /// # Examples
 /// ```no_run
-/// use shai_hulud_v2_checker::network::download_list_of_affected_packages;
+/// use std::time::Duration;
+/// use shai_hulud_v2_checker::network::{download_list_of_affected_packages, AdvisorySource, AFFECTED_PACKAGES_URL, DEFAULT_MAX_AGE_SECS};
 ///
-/// let affected = download_list_of_affected_packages();
-/// if let Some(versions) = affected.get("some-package") {
-///     println!("Vulnerable versions: {:?}", versions);
+/// let source = AdvisorySource {
+///     url: AFFECTED_PACKAGES_URL,
+///     advisory_file: None,
+///     offline: false,
+///     max_age: Duration::from_secs(DEFAULT_MAX_AGE_SECS),
+/// };
+/// let affected = download_list_of_affected_packages(&source);
+/// if let Some(record) = affected.get("some-package") {
+///     println!("Vulnerable versions: {:?}", record.version);
 /// }
 /// ```
-pub fn download_list_of_affected_packages() -> HashMap<String, Vec<String>> {
-    let url = AFFECTED_PACKAGES_URL;
-    let mut response = match ureq::get(url).call() {
-        Ok(r) => r,
-        Err(e) => {
+pub fn download_list_of_affected_packages(
+    source: &AdvisorySource,
+) -> HashMap<String, PackageVulnerableRecord> {
+    let csv_text = fetch_advisory_csv(source);
+
+    let mut csv_reader = csv::ReaderBuilder::new().from_reader(csv_text.as_bytes());
+    csv_reader
+        .deserialize()
+        .collect::<Result<Vec<PackageVulnerableRecord>, _>>()
+        .expect("Can't parse csv file!")
+        .into_iter()
+        .map(|r| (r.package.clone(), r))
+        .collect()
+}
+
+/// Resolves the raw advisory CSV text from `source`, using the on-disk cache
+/// and the network as described on `download_list_of_affected_packages`.
+fn fetch_advisory_csv(source: &AdvisorySource) -> String {
+    if let Some(advisory_file) = source.advisory_file {
+        println!(
+            "📄 Reading the list of affected packages from '{}' ...",
+            advisory_file.display()
+        );
+        return fs::read_to_string(advisory_file).unwrap_or_else(|e| {
             eprintln!(
-                "Failed to download from url '{}' the list of affected packages. Detailed error: \n{}",
-                url, e
+                "Failed to read advisory file '{}'. Detailed error: \n{}",
+                advisory_file.display(),
+                e
             );
             process::exit(1);
+        });
+    }
+
+    let cache_path = advisory_cache_path();
+
+    if source.offline {
+        println!("📦 Offline mode: using the cached list of affected packages");
+        return read_cache(&cache_path).unwrap_or_else(|| {
+            eprintln!(
+                "Error: --offline was passed but no cached advisory list was found at '{}'.",
+                cache_path.display()
+            );
+            process::exit(1);
+        }).csv;
+    }
+
+    if let Some(cache) = read_cache(&cache_path) {
+        let age = Utc::now().signed_duration_since(cache.fetched_at);
+        if age.to_std().map(|age| age <= source.max_age).unwrap_or(false) {
+            println!(
+                "📦 Using cached list of affected packages (fetched {})",
+                cache.fetched_at
+            );
+            return cache.csv;
         }
-    };
-    let response_status = response.status().as_u16();
-    let response_body = response.body_mut();
+    }
 
+    match download_csv(source.url) {
+        Ok(csv) => {
+            write_cache(&cache_path, &csv);
+            csv
+        }
+        Err(e) => {
+            if let Some(cache) = read_cache(&cache_path) {
+                eprintln!(
+                    "⚠️  {} Falling back to the cached advisory list from {}.",
+                    e, cache.fetched_at
+                );
+                return cache.csv;
+            }
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Downloads the advisory CSV from `url`, returning a human-readable error
+/// message (rather than exiting) so the caller can decide whether to fall
+/// back to a cached copy.
+fn download_csv(url: &str) -> Result<String, String> {
     println!(
         "⏳ Downloading the list of affected packages from '{}' ...",
         url
     );
 
+    let mut response = ureq::get(url).call().map_err(|e| {
+        format!(
+            "Failed to download from url '{}' the list of affected packages. Detailed error: \n{}",
+            url, e
+        )
+    })?;
+
+    let response_status = response.status().as_u16();
+    let response_body = response.body_mut();
+
     if response_status != 200 {
-        let error_text = response_body.read_to_string().unwrap_or(String::from(
-            "Couldn't transform http content to text sorry...",
-        ));
-        eprintln!(
+        let error_text = response_body
+            .read_to_string()
+            .unwrap_or(String::from("Couldn't transform http content to text sorry..."));
+        return Err(format!(
             "Failed to download from url '{}' the list of affected packages. HTTP Status: {}, HTTP content {}",
-            url,
-            response.status(),
-            error_text
-        );
-        process::exit(1);
+            url, response_status, error_text
+        ));
     }
 
-    let response_text = match response_body.read_to_string() {
-        Ok(t) => t,
-        Err(e) => {
-            eprintln!(
-                "Failed to decode response text from url '{}' . Detailed error: \n{}",
-                url, e
-            );
-            process::exit(1);
-        }
+    response_body.read_to_string().map_err(|e| {
+        format!(
+            "Failed to decode response text from url '{}' . Detailed error: \n{}",
+            url, e
+        )
+    })
+}
+
+/// Path to the on-disk advisory cache file, under the OS cache directory
+/// (falling back to the system temp directory if that can't be determined).
+fn advisory_cache_path() -> PathBuf {
+    let cache_dir = dirs::cache_dir().unwrap_or_else(std::env::temp_dir);
+    cache_dir
+        .join("shai_hulud_v2_checker")
+        .join("advisory_cache.json")
+}
+
+/// Reads and parses the on-disk advisory cache, if present and well-formed.
+fn read_cache(cache_path: &Path) -> Option<AdvisoryCache> {
+    let contents = fs::read_to_string(cache_path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Writes `csv` to the on-disk advisory cache, stamped with the current
+/// time. Failures are non-fatal: caching is an optimization, not a
+/// requirement for the scan to proceed.
+fn write_cache(cache_path: &Path, csv: &str) {
+    let cache = AdvisoryCache {
+        fetched_at: Utc::now(),
+        csv: csv.to_string(),
     };
 
-    let mut csv_reader = csv::ReaderBuilder::new().from_reader(response_text.as_bytes());
-    csv_reader
-        .deserialize()
-        .collect::<Result<Vec<PackageVulnerableRecord>, _>>()
-        .expect("Can't parse csv file!")
-        .into_iter()
-        .map(|r| (r.package, r.version))
-        .collect()
+    if let Some(parent) = cache_path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            eprintln!("⚠️  Could not create advisory cache directory: {}", e);
+            return;
+        }
+    }
+
+    match serde_json::to_string(&cache) {
+        Ok(json) => {
+            if let Err(e) = fs::write(cache_path, json) {
+                eprintln!("⚠️  Could not write advisory cache: {}", e);
+            }
+        }
+        Err(e) => eprintln!("⚠️  Could not serialize advisory cache: {}", e),
+    }
 }
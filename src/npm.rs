@@ -1,22 +1,35 @@
-//! NPM CLI integration utilities.
+//! NPM registry and CLI integration utilities.
 //!
-//! This module provides functions to interact with the NPM command-line tool,
-//! including checking for NPM installation and retrieving package metadata.
+//! This module provides functions to retrieve package metadata, preferring a
+//! direct HTTPS request to the public npm registry, and checking for NPM
+//! installation on the system PATH.
 
-use crate::models::package::PackageView;
+use crate::models::package::{DistInfo, PackageView};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Deserialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
 use std::process::Command;
 
-/// Retrieves detailed metadata for an NPM package using `npm view`.
+/// Base URL of the public npm registry's package metadata API.
+const NPM_REGISTRY_BASE_URL: &str = "https://registry.npmjs.org";
+
+/// Retrieves detailed metadata for an NPM package.
 ///
-/// This function executes `npm view <package_name> --json` asynchronously to fetch
-/// package information including publication times for all versions.
+/// This queries `https://registry.npmjs.org/<package>` directly, which is
+/// far faster than spawning `npm view` per package and doesn't require npm
+/// to be installed at all. If the registry request fails (offline, rate
+/// limited, or the package lives on a private registry not reachable at that
+/// URL) and npm is available on PATH, this falls back to shelling out to
+/// `npm view <package_name> --json`.
 ///
 /// # Arguments
 /// * `package_name` - The name of the NPM package to query
 ///
 /// # Returns
 /// * `Some(PackageView)` - If the package exists and data was successfully retrieved
-/// * `None` - If the package name is empty, the command fails, or JSON parsing fails
+/// * `None` - If the package name is empty, or both the registry request and
+///   the npm fallback (if attempted) failed
 ///
 /// # Examples
 /// ```no_run
@@ -33,6 +46,55 @@ pub async fn get_npm_package_view(package_name: &str) -> Option<PackageView> {
         return None;
     }
 
+    if let Some(view) = fetch_from_registry(package_name).await {
+        return Some(view);
+    }
+
+    if is_npm_installed() {
+        return fetch_via_npm_view(package_name).await;
+    }
+
+    None
+}
+
+/// Fetches package metadata directly from the public npm registry's HTTP API.
+///
+/// Scoped package names (e.g. `@babel/core`) have their `/` URL-encoded as
+/// `%2F`, matching how the registry expects scoped package paths.
+///
+/// The blocking `ureq` request runs on `smol`'s blocking thread pool via
+/// `smol::unblock` so it doesn't stall the async executor running the
+/// concurrent per-package scans.
+async fn fetch_from_registry(package_name: &str) -> Option<PackageView> {
+    let url = format!(
+        "{}/{}",
+        NPM_REGISTRY_BASE_URL,
+        encode_package_name(package_name)
+    );
+
+    smol::unblock(move || {
+        let mut response = ureq::get(&url).call().ok()?;
+        if response.status().as_u16() != 200 {
+            return None;
+        }
+        response.body_mut().read_json::<PackageView>().ok()
+    })
+    .await
+}
+
+/// URL-encodes a package name for use in a registry request path, escaping
+/// the `/` that separates a scope from its package name (e.g.
+/// `@babel/core` -> `@babel%2Fcore`).
+fn encode_package_name(package_name: &str) -> String {
+    package_name.replace('/', "%2F")
+}
+
+/// Retrieves package metadata by shelling out to `npm view <package_name> --json`.
+///
+/// This is the fallback path used when the direct registry request fails,
+/// which covers private registries configured in `.npmrc` that aren't
+/// reachable at the public registry URL.
+async fn fetch_via_npm_view(package_name: &str) -> Option<PackageView> {
     let output = smol::process::Command::new("npm")
         .arg("view")
         .arg(package_name)
@@ -49,6 +111,70 @@ pub async fn get_npm_package_view(package_name: &str) -> Option<PackageView> {
     serde_json::from_str(&info).ok()
 }
 
+/// Downloads the tarball at `resolved_url` and computes its SRI hashes.
+///
+/// Returns both a `sha512-...` and a `sha1-...` digest of the raw tarball
+/// bytes, since lock files predating the `integrity` field only recorded
+/// SHA-1. Returning both lets the caller compare against whichever
+/// algorithm the lock file happens to have recorded.
+///
+/// The blocking download and hashing runs on `smol`'s blocking thread pool
+/// via `smol::unblock`, same as the other registry requests in this module.
+pub async fn compute_tarball_integrity(resolved_url: &str) -> Option<Vec<String>> {
+    let url = resolved_url.to_string();
+
+    smol::unblock(move || {
+        let mut response = ureq::get(&url).call().ok()?;
+        if response.status().as_u16() != 200 {
+            return None;
+        }
+
+        let bytes = response.body_mut().read_to_vec().ok()?;
+
+        let sha512_digest = Sha512::digest(&bytes);
+        let sha1_digest = Sha1::digest(&bytes);
+
+        Some(vec![
+            format!("sha512-{}", STANDARD.encode(sha512_digest)),
+            format!("sha1-{}", STANDARD.encode(sha1_digest)),
+        ])
+    })
+    .await
+}
+
+/// Fetches the npm registry's stated distribution metadata (the
+/// `integrity`/`shasum` the registry itself reports) for one exact package
+/// version, by querying the registry's per-version manifest endpoint.
+///
+/// This is the fallback integrity source used when a lock file entry has
+/// no `resolved` tarball URL to re-download and hash directly.
+pub async fn fetch_package_version_dist(package_name: &str, version: &str) -> Option<DistInfo> {
+    let url = format!(
+        "{}/{}/{}",
+        NPM_REGISTRY_BASE_URL,
+        encode_package_name(package_name),
+        version
+    );
+
+    smol::unblock(move || {
+        let mut response = ureq::get(&url).call().ok()?;
+        if response.status().as_u16() != 200 {
+            return None;
+        }
+        response.body_mut().read_json::<VersionManifest>().ok()
+    })
+    .await
+    .map(|manifest| manifest.dist)
+}
+
+/// A single npm registry version manifest, as returned by
+/// `GET /<package>/<version>`.
+#[derive(Debug, Deserialize)]
+struct VersionManifest {
+    #[serde(default)]
+    dist: DistInfo,
+}
+
 /// Checks whether NPM is installed and available in the system PATH.
 ///
 /// This function attempts to execute `npm --version` to verify NPM availability.
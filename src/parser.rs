@@ -1,19 +1,40 @@
-//! JSON parsing utilities for package-lock.json files.
+//! Lock file parsing utilities.
 //!
-//! This module handles reading and parsing NPM package-lock.json files into
+//! This module handles reading and parsing NPM/Yarn/pnpm lock files into
 //! structured data types for vulnerability analysis.
 
-use crate::models::package::NpmLockPackages;
-use std::{fs, path::Path, process};
+use crate::models::package::{NpmLockPackages, PackageInfo, PackageResolution};
+use std::{collections::HashMap, fs, path::Path, process};
 
-/// Parses an NPM package-lock.json file into a structured format.
+/// The lock file formats this tool knows how to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockFileFormat {
+    /// `package-lock.json` / `npm-shrinkwrap.json`
+    Npm,
+    /// `yarn.lock`
+    Yarn,
+    /// `pnpm-lock.yaml`
+    Pnpm,
+}
+
+/// Detects which lock file format `path` is, based on its file name.
 ///
-/// This function reads a package-lock.json file from disk and deserializes it into
-/// the `NpmLockPackages` structure, which contains information about all installed
-/// packages and their versions.
+/// Anything that isn't recognized as Yarn or pnpm falls back to the NPM JSON
+/// format, since that's the format `package-lock.json`/`npm-shrinkwrap.json`
+/// (and any other `-f`/`--json-lock-file` override) use.
+pub fn detect_lock_file_format(path: &Path) -> LockFileFormat {
+    match path.file_name().and_then(|name| name.to_str()) {
+        Some("yarn.lock") => LockFileFormat::Yarn,
+        Some("pnpm-lock.yaml") => LockFileFormat::Pnpm,
+        _ => LockFileFormat::Npm,
+    }
+}
+
+/// Parses a lock file into a structured format, auto-detecting whether it's
+/// an NPM, Yarn, or pnpm lock file from its file name.
 ///
 /// # Arguments
-/// * `path` - The filesystem path to the package-lock.json file
+/// * `path` - The filesystem path to the lock file
 ///
 /// # Returns
 /// An `NpmLockPackages` struct containing all parsed package information
@@ -22,19 +43,20 @@ use std::{fs, path::Path, process};
 /// This function will exit the process (via `process::exit(1)`) if:
 /// - The file doesn't exist
 /// - The path is not a valid file
-/// - The file cannot be read
-/// - The JSON cannot be parsed
+///
+/// It will panic if the file cannot be read or its contents can't be parsed
+/// as the detected format.
 ///
 /// # Examples
 /// ```no_run
 /// use std::path::Path;
-/// use shai_hulud_v2_checker::parser::parse_npm_json;
+/// use shai_hulud_v2_checker::parser::parse_lock_file;
 ///
 /// let path = Path::new("package-lock.json");
-/// let packages = parse_npm_json(path);
+/// let packages = parse_lock_file(path);
 /// println!("Found {} packages", packages.packages.len());
 /// ```
-pub fn parse_npm_json(path: &Path) -> NpmLockPackages {
+pub fn parse_lock_file(path: &Path) -> NpmLockPackages {
     if !path.exists() || !path.is_file() {
         eprintln!(
             "File '{}' doesn't exists or is not a valid file aborting",
@@ -47,6 +69,190 @@ pub fn parse_npm_json(path: &Path) -> NpmLockPackages {
         path.to_string_lossy()
     );
 
+    match detect_lock_file_format(path) {
+        LockFileFormat::Npm => parse_npm_json(path),
+        LockFileFormat::Yarn => parse_yarn_lock(path),
+        LockFileFormat::Pnpm => parse_pnpm_lock(path),
+    }
+}
+
+/// Parses an NPM `package-lock.json`/`npm-shrinkwrap.json` file into a
+/// structured format.
+///
+/// This function reads the file from disk and deserializes it into the
+/// `NpmLockPackages` structure, which contains information about all
+/// installed packages and their versions.
+///
+/// # Panics
+/// This function will panic if the file cannot be read or the JSON cannot be
+/// parsed.
+pub fn parse_npm_json(path: &Path) -> NpmLockPackages {
     let json_lock_content = fs::read_to_string(path).expect("Failed to read json lock file");
     serde_json::from_str(&json_lock_content).expect("Failed to parse JSON")
 }
+
+/// Parses a `yarn.lock` file into a structured format.
+///
+/// Yarn's lock file format is a custom, line-based format rather than JSON:
+/// a header line lists one or more quoted `name@range` specifiers separated
+/// by commas and ending in `:`, followed by indented fields for that
+/// resolved entry, one of which is `version "x.y.z"`. All specifiers in a
+/// header share the same resolved version.
+///
+/// # Panics
+/// This function will panic if the file cannot be read.
+pub fn parse_yarn_lock(path: &Path) -> NpmLockPackages {
+    let content = fs::read_to_string(path).expect("Failed to read yarn.lock file");
+
+    let mut packages: HashMap<String, PackageInfo> = HashMap::new();
+    let mut current_names: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(' ') {
+            // Header line, e.g. `"@babel/core@^7.0.0", "@babel/core@^7.1.0":`
+            current_names = line
+                .trim_end_matches(':')
+                .split(',')
+                .filter_map(|spec| {
+                    let spec = spec.trim().trim_matches('"');
+                    spec.rfind('@').map(|at| spec[..at].to_string())
+                })
+                .collect();
+            continue;
+        }
+
+        let Some(version) = line.trim_start().strip_prefix("version ") else {
+            continue;
+        };
+        let version = version.trim().trim_matches('"').to_string();
+
+        for name in &current_names {
+            let entry = packages.entry(name.clone()).or_default();
+            let resolution = PackageResolution {
+                version: version.clone(),
+                integrity: None,
+                resolved: None,
+            };
+            if !entry.resolutions.contains(&resolution) {
+                entry.resolutions.push(resolution);
+            }
+        }
+    }
+
+    NpmLockPackages { packages }
+}
+
+/// Parses a `pnpm-lock.yaml` file into a structured format.
+///
+/// pnpm's lock file is YAML with a top-level `packages` map keyed by
+/// `/name@version` (scoped names keep their leading `@`, e.g.
+/// `/@babel/core@7.12.3`).
+///
+/// # Panics
+/// This function will panic if the file cannot be read or the YAML cannot
+/// be parsed.
+pub fn parse_pnpm_lock(path: &Path) -> NpmLockPackages {
+    let content = fs::read_to_string(path).expect("Failed to read pnpm-lock.yaml file");
+    let raw: PnpmLockFile =
+        serde_yaml::from_str(&content).expect("Failed to parse pnpm-lock.yaml");
+
+    let mut packages: HashMap<String, PackageInfo> = HashMap::new();
+    for key in raw.packages.keys() {
+        let Some((name, version)) = split_pnpm_package_key(key) else {
+            continue;
+        };
+
+        let entry = packages.entry(name).or_default();
+        let resolution = PackageResolution {
+            version,
+            integrity: None,
+            resolved: None,
+        };
+        if !entry.resolutions.contains(&resolution) {
+            entry.resolutions.push(resolution);
+        }
+    }
+
+    NpmLockPackages { packages }
+}
+
+/// Splits a pnpm `packages` map key like `/@babel/core@7.12.3` into its
+/// package name and version.
+///
+/// Keys for packages with peer dependencies carry a trailing qualifier
+/// after the version, e.g. `/eslint-plugin-import@2.26.0_eslint@8.28.0`
+/// (older lockfile versions) or `/eslint-plugin-import@2.26.0(eslint@8.28.0)`
+/// (newer ones). Taking the *last* `@` would land on the peer's own
+/// version instead, so the name/version boundary is found by taking the
+/// first `@` after the package name (skipping the scope's `@` for scoped
+/// packages), then anything from the first `_` or `(` after that is
+/// dropped as a peer qualifier.
+fn split_pnpm_package_key(key: &str) -> Option<(String, String)> {
+    let trimmed = key.trim_start_matches('/');
+
+    let name_end = match trimmed.strip_prefix('@') {
+        Some(rest) => rest.find('@').map(|idx| idx + 1)?,
+        None => trimmed.find('@')?,
+    };
+
+    let name = trimmed[..name_end].to_string();
+    let version_and_qualifiers = &trimmed[name_end + 1..];
+    let version_end = version_and_qualifiers
+        .find(['_', '('])
+        .unwrap_or(version_and_qualifiers.len());
+
+    Some((name, version_and_qualifiers[..version_end].to_string()))
+}
+
+#[derive(Debug, serde::Deserialize, Default)]
+struct PnpmLockFile {
+    #[serde(default)]
+    packages: HashMap<String, serde_yaml::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_pnpm_package_key;
+
+    #[test]
+    fn unscoped_package_without_qualifier() {
+        let (name, version) = split_pnpm_package_key("/lodash@4.17.21").unwrap();
+        assert_eq!(name, "lodash");
+        assert_eq!(version, "4.17.21");
+    }
+
+    #[test]
+    fn scoped_package_without_qualifier() {
+        let (name, version) = split_pnpm_package_key("/@babel/core@7.12.3").unwrap();
+        assert_eq!(name, "@babel/core");
+        assert_eq!(version, "7.12.3");
+    }
+
+    #[test]
+    fn underscore_peer_qualifier_is_stripped() {
+        let (name, version) =
+            split_pnpm_package_key("/eslint-plugin-import@2.26.0_eslint@8.28.0").unwrap();
+        assert_eq!(name, "eslint-plugin-import");
+        assert_eq!(version, "2.26.0");
+    }
+
+    #[test]
+    fn parenthesized_peer_qualifier_is_stripped() {
+        let (name, version) =
+            split_pnpm_package_key("/eslint-plugin-import@2.26.0(eslint@8.28.0)").unwrap();
+        assert_eq!(name, "eslint-plugin-import");
+        assert_eq!(version, "2.26.0");
+    }
+
+    #[test]
+    fn scoped_package_with_peer_qualifier() {
+        let (name, version) =
+            split_pnpm_package_key("/@typescript-eslint/parser@5.45.0_eslint@8.28.0").unwrap();
+        assert_eq!(name, "@typescript-eslint/parser");
+        assert_eq!(version, "5.45.0");
+    }
+}
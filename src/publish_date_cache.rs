@@ -0,0 +1,130 @@
+//! On-disk cache for npm package publish-date lookups.
+//!
+//! `check_possible_vulnerable_packages` needs every installed version's
+//! publish timestamp, which costs one registry/`npm view` round trip per
+//! package. This cache persists those timestamps (bincode-encoded, under the
+//! OS cache dir, alongside the advisory cache in `network.rs`) keyed by
+//! `name@version`, so re-scanning the same lock file only has to query the
+//! registry for name/version pairs it hasn't already seen within the TTL.
+
+use chrono::Utc;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Default maximum age, in seconds, of a cached publish-date entry before
+/// it's considered stale and re-fetched.
+pub const DEFAULT_MAX_AGE_SECS: u64 = 7 * 24 * 3600;
+
+/// One cached `name@version` publish-date lookup.
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+struct CachedEntry {
+    fetched_at_unix: i64,
+    published_at: String,
+}
+
+/// Controls how `check_possible_vulnerable_packages` consults and updates
+/// the publish-date cache, set via `--no-cache`/`--refresh-cache`.
+#[derive(Debug, Clone, Copy)]
+pub struct PublishDateCacheOptions {
+    /// Consult and update the on-disk cache at all. Disabled by `--no-cache`.
+    pub enabled: bool,
+    /// Treat every cached entry as stale, forcing a fresh lookup, but still
+    /// write the refreshed result back. Set by `--refresh-cache`.
+    pub refresh: bool,
+    /// Maximum age of a cached entry before it's considered stale.
+    pub max_age: Duration,
+}
+
+/// The on-disk cache of `name@version -> publish timestamp` lookups.
+#[derive(Debug, Default, bincode::Encode, bincode::Decode)]
+pub struct PublishDateCache {
+    entries: HashMap<String, CachedEntry>,
+}
+
+impl PublishDateCache {
+    /// Loads the cache from disk, starting empty if it's missing, corrupt,
+    /// or `options.enabled` is false.
+    pub fn load(options: &PublishDateCacheOptions) -> Self {
+        if !options.enabled {
+            return Self::default();
+        }
+
+        read_cache(&cache_path()).unwrap_or_default()
+    }
+
+    /// Looks up the publish timestamp for `package@version`, returning
+    /// `None` on a cache miss or an entry older than `max_age`.
+    pub fn get(&self, package: &str, version: &str, max_age: Duration) -> Option<&str> {
+        let entry = self.entries.get(&cache_key(package, version))?;
+        let age = Utc::now().timestamp() - entry.fetched_at_unix;
+        if age < 0 || age as u64 > max_age.as_secs() {
+            return None;
+        }
+        Some(&entry.published_at)
+    }
+
+    /// Records the publish timestamp for `package@version`, stamped with the
+    /// current time.
+    pub fn insert(&mut self, package: &str, version: &str, published_at: String) {
+        self.entries.insert(
+            cache_key(package, version),
+            CachedEntry {
+                fetched_at_unix: Utc::now().timestamp(),
+                published_at,
+            },
+        );
+    }
+
+    /// Writes the cache to disk. A no-op when `options.enabled` is false.
+    /// Failures are non-fatal: caching is an optimization, not a requirement
+    /// for the scan to have succeeded.
+    pub fn save(&self, options: &PublishDateCacheOptions) {
+        if !options.enabled {
+            return;
+        }
+
+        let cache_path = cache_path();
+        if let Some(parent) = cache_path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                eprintln!("⚠️  Could not create publish-date cache directory: {}", e);
+                return;
+            }
+        }
+
+        match bincode::encode_to_vec(self, bincode::config::standard()) {
+            Ok(bytes) => {
+                if let Err(e) = fs::write(&cache_path, bytes) {
+                    eprintln!("⚠️  Could not write publish-date cache: {}", e);
+                }
+            }
+            Err(e) => eprintln!("⚠️  Could not serialize publish-date cache: {}", e),
+        }
+    }
+}
+
+/// The cache key for one `package@version` pair.
+fn cache_key(package: &str, version: &str) -> String {
+    format!("{package}@{version}")
+}
+
+/// Path to the on-disk publish-date cache file, under the OS cache directory
+/// (falling back to the system temp directory if that can't be determined).
+fn cache_path() -> PathBuf {
+    let cache_dir = dirs::cache_dir().unwrap_or_else(std::env::temp_dir);
+    cache_dir
+        .join("shai_hulud_v2_checker")
+        .join("publish_date_cache.bin")
+}
+
+/// Reads and decodes the on-disk publish-date cache, if present and
+/// well-formed.
+fn read_cache(cache_path: &Path) -> Option<PublishDateCache> {
+    let bytes = fs::read(cache_path).ok()?;
+    bincode::decode_from_slice(&bytes, bincode::config::standard())
+        .ok()
+        .map(|(cache, _)| cache)
+}
@@ -4,26 +4,203 @@
 //! 1. Known vulnerabilities - matching against a curated list of affected packages
 //! 2. Possible vulnerabilities - checking if packages were published after the attack date
 
-use crate::models::package::NpmLockPackages;
-use crate::npm::get_npm_package_view;
+use crate::models::package::{NpmLockPackages, PackageVulnerableRecord};
+use crate::npm::{compute_tarball_integrity, fetch_package_version_dist, get_npm_package_view};
+use crate::publish_date_cache::{PublishDateCache, PublishDateCacheOptions};
 use async_lock::Mutex;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use chrono::{DateTime, Utc};
 use std::{collections::HashMap, sync::Arc};
 
 /// The timestamp when the Shai Hulud V2 attack was detected
 const ATTACK_DATE: &str = "2025-11-24T03:16:26.000Z";
 
+/// SRI hash algorithms, weakest to strongest. When comparing two sets of
+/// hashes that may each carry more than one algorithm, only the strongest
+/// algorithm present on *both* sides is compared — the way nixpkgs'
+/// prefetch tooling reconciles mixed-algorithm sources — so a legacy
+/// SHA-1-only lock file entry isn't flagged as tampered just because the
+/// registry also reports a SHA-512 digest for the same content.
+const ALGORITHM_STRENGTH: [&str; 2] = ["sha1", "sha512"];
+
+/// One installed package/version whose recorded lock file integrity hash
+/// doesn't match the hash recomputed (or registry-reported) for that exact
+/// resolution — the signal a Shai-Hulud-style tarball swap would leave.
+#[derive(Debug, Clone)]
+pub struct TamperedIntegrityFinding {
+    pub package: String,
+    pub version: String,
+    pub recorded_integrity: String,
+    pub actual_integrity: String,
+}
+
+/// Parses an SRI hash string (e.g. `"sha512-abc=="`, possibly several
+/// space-separated hashes in one string) into `(algorithm, value)` pairs.
+fn parse_hashes(hashes: &[String]) -> HashMap<&str, &str> {
+    hashes
+        .iter()
+        .flat_map(|hash| hash.split_whitespace())
+        .filter_map(|hash| hash.split_once('-'))
+        .collect()
+}
+
+/// Compares `recorded` (from the lock file) against `actual` (recomputed
+/// from the tarball, or reported by the registry), using only the
+/// strongest algorithm both sides have in common. Returns `None` when the
+/// two sides share no comparable algorithm, since that's not evidence of
+/// tampering — just a lock file and a registry that disagree on format.
+fn hashes_match(recorded: &HashMap<&str, &str>, actual: &HashMap<&str, &str>) -> Option<bool> {
+    ALGORITHM_STRENGTH.iter().rev().find_map(|algorithm| {
+        match (recorded.get(algorithm), actual.get(algorithm)) {
+            (Some(expected), Some(got)) => Some(expected == got),
+            _ => None,
+        }
+    })
+}
+
+/// Converts a legacy hex-encoded SHA-1 `shasum` into an SRI-formatted
+/// `"sha1-<base64>"` string, so it can be compared the same way as a
+/// modern `integrity` field.
+fn shasum_to_sri(shasum: &str) -> Option<String> {
+    if !shasum.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let bytes: Vec<u8> = (0..shasum.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&shasum[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    Some(format!("sha1-{}", STANDARD.encode(bytes)))
+}
+
+/// Verifies installed packages' recorded lock file integrity hashes against
+/// the actual tarball content.
+///
+/// For each installed resolution that recorded both a version and an
+/// `integrity` hash, this re-derives the "real" hash in one of two ways:
+/// 1. If a `resolved` tarball URL was recorded, download it and recompute
+///    its SHA-512/SHA-1 digests directly — the most direct evidence, since
+///    it doesn't depend on what the registry claims *now*.
+/// 2. Otherwise, fall back to asking the registry what it reports as the
+///    `dist.integrity`/`dist.shasum` for that exact package version.
+///
+/// A mismatch is reported as a `TamperedIntegrityFinding`; lookups that
+/// fail, or that share no comparable hash algorithm, are silently skipped
+/// rather than treated as evidence of tampering.
+///
+/// Every resolution of a package (not just the first) is checked
+/// independently, since a package hoisted to multiple places in the tree
+/// can have more than one installed version, and `PackageResolution` keeps
+/// each one's version/integrity/resolved paired together so this can't
+/// check a version against the wrong resolution's hash.
+///
+/// # Arguments
+/// * `packages` - The complete list of installed packages to verify
+/// * `threads_num` - The maximum number of concurrent lookups
+/// * `offline` - Skip this check entirely rather than downloading tarballs
+///   or querying the registry, for air-gapped `--offline` use
+pub async fn check_tampered_integrity(
+    packages: &NpmLockPackages,
+    threads_num: usize,
+    offline: bool,
+) -> Vec<TamperedIntegrityFinding> {
+    if offline {
+        println!("📦 Offline mode: skipping tampered-integrity check (requires network access)");
+        return Vec::new();
+    }
+
+    let semaphore = Arc::new(async_lock::Semaphore::new(threads_num.max(1)));
+    let findings = Arc::new(Mutex::new(Vec::new()));
+
+    let mut tasks = Vec::new();
+
+    for (package_name, info) in &packages.packages {
+        for resolution in &info.resolutions {
+            let Some(recorded_integrity) = resolution.integrity.clone() else {
+                continue;
+            };
+
+            let package_name = package_name.clone();
+            let version = resolution.version.clone();
+            let resolved = resolution.resolved.clone();
+            let semaphore_clone = Arc::clone(&semaphore);
+            let findings_clone = Arc::clone(&findings);
+
+            let task = smol::spawn(async move {
+                let _permit = semaphore_clone.acquire().await;
+
+                let actual_hashes = match &resolved {
+                    Some(resolved_url) => compute_tarball_integrity(resolved_url).await,
+                    None => {
+                        fetch_package_version_dist(&package_name, &version)
+                            .await
+                            .map(|dist| {
+                                let mut hashes = Vec::new();
+                                if let Some(integrity) = dist.integrity {
+                                    hashes.push(integrity);
+                                }
+                                if let Some(sri) = dist.shasum.as_deref().and_then(shasum_to_sri) {
+                                    hashes.push(sri);
+                                }
+                                hashes
+                            })
+                    }
+                };
+
+                let Some(actual_hashes) = actual_hashes else {
+                    return;
+                };
+
+                let recorded = parse_hashes(std::slice::from_ref(&recorded_integrity));
+                let actual = parse_hashes(&actual_hashes);
+
+                if hashes_match(&recorded, &actual) == Some(false) {
+                    println!(
+                        "\t❗ Integrity mismatch for '{}'@{}: lock file says '{}', actual is '{}'",
+                        package_name, version, recorded_integrity, actual_hashes.join(" ")
+                    );
+                    findings_clone.lock().await.push(TamperedIntegrityFinding {
+                        package: package_name,
+                        version,
+                        recorded_integrity,
+                        actual_integrity: actual_hashes.join(" "),
+                    });
+                }
+            });
+
+            tasks.push(task);
+        }
+    }
+
+    for task in tasks {
+        task.await;
+    }
+
+    Arc::try_unwrap(findings).unwrap().into_inner()
+}
+
 /// Checks packages for possible vulnerabilities based on publication date.
 ///
 /// This function performs concurrent scans of all installed packages, checking if any
 /// versions were published after the Shai Hulud V2 attack date. Packages published
 /// after this date are flagged as potentially vulnerable and require manual review.
 ///
-/// The function uses async concurrency with a semaphore limiting to 5 concurrent
-/// tasks to avoid overwhelming the NPM registry or the local system.
+/// The function uses async concurrency with a semaphore limiting the number of
+/// concurrent tasks to `threads_num`, to avoid overwhelming the NPM registry
+/// or the local system.
 ///
 /// # Arguments
 /// * `packages` - The complete list of installed packages to scan
+/// * `threads_num` - The maximum number of concurrent npm view lookups
+/// * `cache` - The on-disk publish-date cache, consulted before any registry
+///   lookup and updated with every freshly fetched date
+/// * `cache_options` - Whether the cache is enabled, should be force-refreshed,
+///   and how old a cached entry may be before it's considered stale
+/// * `offline` - Skip the registry/`npm view` lookup for any package not
+///   fully covered by the cache, marking it `skipped_scan = true` instead,
+///   for air-gapped `--offline` use
 ///
 /// # Returns
 /// A tuple containing:
@@ -31,6 +208,8 @@ const ATTACK_DATE: &str = "2025-11-24T03:16:26.000Z";
 /// * `NpmLockPackages` - Packages that may be vulnerable (published after attack date)
 ///
 /// # Behavior
+/// - A package whose installed versions are all present in the cache skips
+///   the registry/`npm view` lookup entirely
 /// - Packages that cannot be queried via NPM are marked with `skipped_scan = true`
 /// - For each package, all installed versions are checked against their publish dates
 /// - If any version was published after the attack date, the entire package is flagged
@@ -38,16 +217,25 @@ const ATTACK_DATE: &str = "2025-11-24T03:16:26.000Z";
 ///
 /// # Examples
 /// ```no_run
+/// use std::{sync::Arc, time::Duration};
+/// use async_lock::Mutex;
 /// use shai_hulud_v2_checker::scanner::check_possible_vulnerable_packages;
 /// use shai_hulud_v2_checker::models::package::NpmLockPackages;
+/// use shai_hulud_v2_checker::publish_date_cache::{PublishDateCache, PublishDateCacheOptions};
 ///
 /// # async fn example(packages: NpmLockPackages) {
-/// let (safe, vulnerable) = check_possible_vulnerable_packages(packages).await;
+/// let cache_options = PublishDateCacheOptions { enabled: true, refresh: false, max_age: Duration::from_secs(3600) };
+/// let cache = Arc::new(Mutex::new(PublishDateCache::load(&cache_options)));
+/// let (safe, vulnerable) = check_possible_vulnerable_packages(packages, 5, &cache, cache_options, false).await;
 /// println!("Possibly vulnerable: {}", vulnerable.packages.len());
 /// # }
 /// ```
 pub async fn check_possible_vulnerable_packages(
     packages: NpmLockPackages,
+    threads_num: usize,
+    cache: &Arc<Mutex<PublishDateCache>>,
+    cache_options: PublishDateCacheOptions,
+    offline: bool,
 ) -> (NpmLockPackages, NpmLockPackages) {
     let attack_datetime: DateTime<Utc> = ATTACK_DATE.parse().expect("Failed to parse attack date");
     let possibly_vulnerable = Arc::new(Mutex::new(NpmLockPackages::new()));
@@ -55,8 +243,8 @@ pub async fn check_possible_vulnerable_packages(
 
     let package_keys: Vec<String> = packages_arc.lock().await.keys().cloned().collect();
 
-    // Create a semaphore to limit concurrent tasks to 5
-    let semaphore = Arc::new(async_lock::Semaphore::new(5));
+    // Create a semaphore to limit concurrent tasks to threads_num
+    let semaphore = Arc::new(async_lock::Semaphore::new(threads_num.max(1)));
     let buffer_lock = Arc::new(Mutex::new(()));
 
     let mut tasks = Vec::new();
@@ -66,6 +254,7 @@ pub async fn check_possible_vulnerable_packages(
         let possibly_vulnerable_clone = Arc::clone(&possibly_vulnerable);
         let semaphore_clone = Arc::clone(&semaphore);
         let buffer_lock_clone = Arc::clone(&buffer_lock);
+        let cache_clone = Arc::clone(cache);
 
         let task = smol::spawn(async move {
             let mut output = String::new();
@@ -77,76 +266,136 @@ pub async fn check_possible_vulnerable_packages(
                 pkg_key
             ));
 
-            let package_view = match get_npm_package_view(&pkg_key).await {
-                Some(pv) => pv,
-                None => {
-                    output.push_str(&format!(
-                        "\t⚠️  Could not retrieve npm view for package '{}', skipping possible vulnerability check.\n",
-                        pkg_key
-                    ));
-                    let mut pkgs = packages_clone.lock().await;
-                    if let Some(pkg_info) = pkgs.get_mut(&pkg_key) {
-                        pkg_info.skipped_scan = true;
+            let package_info = {
+                let pkgs = packages_clone.lock().await;
+                pkgs.get(&pkg_key).cloned()
+            };
+            let Some(package_info) = package_info else {
+                return;
+            };
+
+            // Publish dates already cached (and not stale) for every
+            // installed version mean this package needs no registry lookup.
+            let mut cached_dates: HashMap<String, String> = HashMap::new();
+            if cache_options.enabled && !cache_options.refresh {
+                let cache_guard = cache_clone.lock().await;
+                for ver in package_info.versions() {
+                    if let Some(published_at) =
+                        cache_guard.get(&pkg_key, ver, cache_options.max_age)
+                    {
+                        cached_dates.insert(ver.to_string(), published_at.to_string());
                     }
+                }
+            }
 
-                    // Print before returning
-                    let _buffer_guard = buffer_lock_clone.lock().await;
-                    print!("{}", output);
-                    return;
+            let needs_lookup = package_info
+                .versions()
+                .any(|ver| !cached_dates.contains_key(ver));
+
+            if needs_lookup && offline {
+                output.push_str(&format!(
+                    "\t📦 Offline mode: '{}' isn't fully covered by the publish-date cache, skipping possible vulnerability check.\n",
+                    pkg_key
+                ));
+                let mut pkgs = packages_clone.lock().await;
+                if let Some(pkg_info) = pkgs.get_mut(&pkg_key) {
+                    pkg_info.skipped_scan = true;
                 }
-            };
 
-            output.push_str(&format!(
-                "\t📦 Retrieved npm view for package '{}', checking versions...\n",
-                pkg_key
-            ));
+                let _buffer_guard = buffer_lock_clone.lock().await;
+                print!("{}", output);
+                return;
+            }
 
-            let mut maybe_vulnerable = false;
+            if needs_lookup {
+                let package_view = match get_npm_package_view(&pkg_key).await {
+                    Some(pv) => pv,
+                    None => {
+                        output.push_str(&format!(
+                            "\t⚠️  Could not retrieve npm view for package '{}', skipping possible vulnerability check.\n",
+                            pkg_key
+                        ));
+                        let mut pkgs = packages_clone.lock().await;
+                        if let Some(pkg_info) = pkgs.get_mut(&pkg_key) {
+                            pkg_info.skipped_scan = true;
+                        }
 
-            let package_info = {
-                let pkgs = packages_clone.lock().await;
-                pkgs.get(&pkg_key).cloned()
-            };
+                        // Print before returning
+                        let _buffer_guard = buffer_lock_clone.lock().await;
+                        print!("{}", output);
+                        return;
+                    }
+                };
+
+                output.push_str(&format!(
+                    "\t📦 Retrieved npm view for package '{}', checking versions...\n",
+                    pkg_key
+                ));
 
-            if let Some(package_info) = package_info {
-                for ver in package_info.version.iter() {
-                    let version_created = package_view.time.get(ver);
-                    let version_created = match version_created {
-                        Some(vc) => vc,
-                        None => {
-                            output.push_str(&format!(
-                                "\t⚠️  Could not find creation time for version '{}' of package '{}', skipping this version.\n",
-                                ver, pkg_key
-                            ));
-                            continue;
+                if cache_options.enabled {
+                    let mut cache_guard = cache_clone.lock().await;
+                    for ver in package_info.versions() {
+                        if let Some(published_at) = package_view.time.get(ver) {
+                            cache_guard.insert(&pkg_key, ver, published_at.clone());
+                            cached_dates
+                                .entry(ver.to_string())
+                                .or_insert_with(|| published_at.clone());
                         }
-                    };
+                    }
+                } else {
+                    for ver in package_info.versions() {
+                        if let Some(published_at) = package_view.time.get(ver) {
+                            cached_dates
+                                .entry(ver.to_string())
+                                .or_insert_with(|| published_at.clone());
+                        }
+                    }
+                }
+            } else {
+                output.push_str(&format!(
+                    "\t💾 All installed versions of '{}' found in the publish-date cache.\n",
+                    pkg_key
+                ));
+            }
 
-                    let version_created_datetime: DateTime<Utc> = version_created
-                        .parse()
-                        .expect("Failed to parse version time");
+            let mut maybe_vulnerable = false;
 
-                    if version_created_datetime > attack_datetime {
-                        output.push_str(&format!(
-                            "\t❗ Version '{}' of package '{}' was published on '{}' after the attack date ({}), it may be vulnerable.\n",
-                            ver, pkg_key, version_created, ATTACK_DATE
-                        ));
-                        maybe_vulnerable = true;
-                        break;
-                    } else {
+            for ver in package_info.versions() {
+                let version_created = match cached_dates.get(ver) {
+                    Some(vc) => vc,
+                    None => {
                         output.push_str(&format!(
-                            "\t✅ Version '{}' of package '{}' was published on '{}' before the attack date ({}), it is not vulnerable.\n",
-                            ver, pkg_key, version_created, ATTACK_DATE
+                            "\t⚠️  Could not find creation time for version '{}' of package '{}', skipping this version.\n",
+                            ver, pkg_key
                         ));
+                        continue;
                     }
+                };
+
+                let version_created_datetime: DateTime<Utc> = version_created
+                    .parse()
+                    .expect("Failed to parse version time");
+
+                if version_created_datetime > attack_datetime {
+                    output.push_str(&format!(
+                        "\t❗ Version '{}' of package '{}' was published on '{}' after the attack date ({}), it may be vulnerable.\n",
+                        ver, pkg_key, version_created, ATTACK_DATE
+                    ));
+                    maybe_vulnerable = true;
+                    break;
+                } else {
+                    output.push_str(&format!(
+                        "\t✅ Version '{}' of package '{}' was published on '{}' before the attack date ({}), it is not vulnerable.\n",
+                        ver, pkg_key, version_created, ATTACK_DATE
+                    ));
                 }
+            }
 
-                if maybe_vulnerable {
-                    let mut pkgs = packages_clone.lock().await;
-                    if let Some(value) = pkgs.remove(&pkg_key) {
-                        let mut vuln_pkgs = possibly_vulnerable_clone.lock().await;
-                        vuln_pkgs.packages.insert(pkg_key.clone(), value);
-                    }
+            if maybe_vulnerable {
+                let mut pkgs = packages_clone.lock().await;
+                if let Some(value) = pkgs.remove(&pkg_key) {
+                    let mut vuln_pkgs = possibly_vulnerable_clone.lock().await;
+                    vuln_pkgs.packages.insert(pkg_key.clone(), value);
                 }
             }
 
@@ -178,7 +427,7 @@ pub async fn check_possible_vulnerable_packages(
 /// matching on both package names and version numbers.
 ///
 /// # Arguments
-/// * `vulnerabilities` - HashMap of known vulnerable packages to their affected versions
+/// * `vulnerabilities` - HashMap of known vulnerable packages to their affected versions/integrity hashes
 /// * `packages` - The complete list of installed packages to check
 ///
 /// # Returns
@@ -188,7 +437,14 @@ pub async fn check_possible_vulnerable_packages(
 ///
 /// # Behavior
 /// - Only packages present in both lists are flagged as vulnerable
-/// - Version matching is exact (e.g., "1.2.3" must match exactly)
+/// - Version matching follows npm's own range syntax: each CSV entry is an
+///   OR (`||`) of alternatives, each an AND of `^`/`~`/`=`/`>=`/`<=`/`>`/`<`
+///   comparator terms (see `models::version_matcher::VersionMatcher`). An
+///   entry that isn't valid npm range syntax falls back to plain string
+///   equality.
+/// - A version that doesn't match is still flagged if its recorded
+///   integrity hash matches one of the advisory's known-malicious hashes,
+///   since a republished tarball can carry a rewritten version string.
 /// - Progress and findings are printed to stdout
 /// - Vulnerable packages are removed from the input list and added to the output list
 ///
@@ -196,27 +452,27 @@ pub async fn check_possible_vulnerable_packages(
 /// ```no_run
 /// use std::collections::HashMap;
 /// use shai_hulud_v2_checker::scanner::check_vulnerable_packages;
-/// use shai_hulud_v2_checker::models::package::NpmLockPackages;
-///
-/// let mut vulnerabilities = HashMap::new();
-/// vulnerabilities.insert("bad-package".to_string(), vec!["1.0.0".to_string()]);
+/// use shai_hulud_v2_checker::models::package::{NpmLockPackages, PackageVulnerableRecord};
 ///
+/// # let vulnerabilities: HashMap<String, PackageVulnerableRecord> = HashMap::new();
 /// # let packages = NpmLockPackages::new();
 /// let (safe, vulnerable) = check_vulnerable_packages(&vulnerabilities, packages);
 /// ```
 pub fn check_vulnerable_packages(
-    vulnerabilities: &HashMap<String, Vec<String>>,
+    vulnerabilities: &HashMap<String, PackageVulnerableRecord>,
     mut packages: NpmLockPackages,
 ) -> (NpmLockPackages, NpmLockPackages) {
     let mut vulnerable_packages = NpmLockPackages::new();
-    for (vuln_package, vuln_versions) in vulnerabilities.iter() {
+    for (vuln_package, vuln_record) in vulnerabilities.iter() {
         println!("\n----------------------------------------");
         println!("🔎 Checking package '{}'", vuln_package);
         if let Some(installed_package) = packages.packages.get(vuln_package) {
             println!("⚠️  Vulnerable package found: '{}'", vuln_package);
-            for installed_version in installed_package.version.iter() {
+            let mut version_matched = false;
+            for installed_version in installed_package.versions() {
                 println!("\t🔍 Installed version found: '{}'", installed_version);
-                if vuln_versions.iter().any(|v| v == installed_version) {
+                if vuln_record.version.matches(installed_version) {
+                    version_matched = true;
                     println!(
                         "\t❗ Version '{}' of package '{}' is VULNERABLE!",
                         installed_version, vuln_package
@@ -224,13 +480,37 @@ pub fn check_vulnerable_packages(
                 }
             }
 
-            let vulnerable_package = packages
-                .packages
-                .remove(vuln_package)
-                .expect("Package should exist");
-            vulnerable_packages
-                .packages
-                .insert(vuln_package.clone(), vulnerable_package);
+            let mut integrity_matched = false;
+            if !vuln_record.integrity.is_empty() {
+                for installed_integrity in installed_package.integrities() {
+                    if vuln_record
+                        .integrity
+                        .iter()
+                        .any(|known| known == installed_integrity)
+                    {
+                        integrity_matched = true;
+                        println!(
+                            "\t❗ Installed integrity '{}' of package '{}' matches a known-malicious tarball hash!",
+                            installed_integrity, vuln_package
+                        );
+                    }
+                }
+            }
+
+            if version_matched || integrity_matched {
+                let vulnerable_package = packages
+                    .packages
+                    .remove(vuln_package)
+                    .expect("Package should exist");
+                vulnerable_packages
+                    .packages
+                    .insert(vuln_package.clone(), vulnerable_package);
+            } else {
+                println!(
+                    "✅ Installed version(s) of '{}' are not in the vulnerable range",
+                    vuln_package
+                );
+            }
         } else {
             println!(
                 "✅ Package '{}' not found among installed packages",
@@ -241,3 +521,75 @@ pub fn check_vulnerable_packages(
 
     (packages, vulnerable_packages)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{hashes_match, parse_hashes, shasum_to_sri};
+
+    #[test]
+    fn parse_hashes_splits_algorithm_from_value() {
+        let hashes = vec!["sha512-abc==".to_string()];
+        let parsed = parse_hashes(&hashes);
+        assert_eq!(parsed.get("sha512"), Some(&"abc=="));
+    }
+
+    #[test]
+    fn parse_hashes_handles_multiple_space_separated_hashes() {
+        let hashes = vec!["sha512-abc== sha1-def==".to_string()];
+        let parsed = parse_hashes(&hashes);
+        assert_eq!(parsed.get("sha512"), Some(&"abc=="));
+        assert_eq!(parsed.get("sha1"), Some(&"def=="));
+    }
+
+    #[test]
+    fn parse_hashes_splits_malformed_entries_on_first_dash() {
+        let hashes = vec!["not-a-valid-hash-format".to_string()];
+        let parsed = parse_hashes(&hashes);
+        // Still splits on the first '-', since that's all this function does;
+        // malformed algorithm names just won't match anything real later.
+        assert_eq!(parsed.get("not"), Some(&"a-valid-hash-format"));
+    }
+
+    #[test]
+    fn hashes_match_compares_strongest_shared_algorithm() {
+        let recorded_hashes = ["sha1-old== sha512-good==".to_string()];
+        let actual_hashes = ["sha512-good==".to_string()];
+        let recorded = parse_hashes(&recorded_hashes);
+        let actual = parse_hashes(&actual_hashes);
+        assert_eq!(hashes_match(&recorded, &actual), Some(true));
+    }
+
+    #[test]
+    fn hashes_match_detects_mismatch_on_strongest_shared_algorithm() {
+        let recorded_hashes = ["sha512-good==".to_string()];
+        let actual_hashes = ["sha512-tampered==".to_string()];
+        let recorded = parse_hashes(&recorded_hashes);
+        let actual = parse_hashes(&actual_hashes);
+        assert_eq!(hashes_match(&recorded, &actual), Some(false));
+    }
+
+    #[test]
+    fn hashes_match_returns_none_for_disjoint_algorithm_sets() {
+        let recorded_hashes = ["sha1-abc==".to_string()];
+        let actual_hashes = ["sha512-def==".to_string()];
+        let recorded = parse_hashes(&recorded_hashes);
+        let actual = parse_hashes(&actual_hashes);
+        assert_eq!(hashes_match(&recorded, &actual), None);
+    }
+
+    #[test]
+    fn shasum_to_sri_converts_hex_digest() {
+        // "ab" -> byte 0xab -> base64 "qw=="
+        assert_eq!(shasum_to_sri("ab").as_deref(), Some("sha1-qw=="));
+    }
+
+    #[test]
+    fn shasum_to_sri_rejects_odd_length_input() {
+        assert_eq!(shasum_to_sri("abc"), None);
+    }
+
+    #[test]
+    fn shasum_to_sri_rejects_non_hex_input() {
+        assert_eq!(shasum_to_sri("zz"), None);
+    }
+}
@@ -3,11 +3,26 @@
 //! This module handles all CLI argument parsing and related utilities for the
 //! Shai Hulud V2 vulnerability checker.
 
+use super::output::{FailOnSeverity, OutputFormat};
+use crate::network::{AFFECTED_PACKAGES_URL, DEFAULT_MAX_AGE_SECS};
+use crate::publish_date_cache::DEFAULT_MAX_AGE_SECS as DEFAULT_PUBLISH_DATE_CACHE_MAX_AGE_SECS;
 use clap::Parser;
-use std::{env, path::PathBuf, process};
+use std::{collections::HashSet, env, fs, path::Path, path::PathBuf, process};
+use walkdir::WalkDir;
+
+/// File names recognized as lock files by auto-discovery and recursive scanning.
+const LOCK_FILE_NAMES: [&str; 4] = [
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+];
+
+/// Directory names skipped while recursively walking for lock files.
+const SKIPPED_DIR_NAMES: [&str; 2] = ["node_modules", ".git"];
 
 /// CLI arguments for the Shai Hulud V2 vulnerability checker
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
     /// Path to the package-lock.json file (relative or absolute).
@@ -18,6 +33,66 @@ pub struct Args {
     /// Number of threads to spawn for running npm view commands
     #[arg(short = 't', long = "threads-num", default_value = "5")]
     pub threads_num: usize,
+
+    /// Recursively scan every supported lock file found under `--scan-dir`
+    /// (or the current directory, if that's not passed), skipping
+    /// `node_modules` and `.git`. Each lock file is scanned concurrently,
+    /// bounded by `--threads-num`, and findings are rolled up into a
+    /// workspace-wide summary of unique vulnerable packages.
+    #[arg(short = 'r', long = "recursive")]
+    pub recursive: bool,
+
+    /// Root directory to walk when `--recursive` is passed. Defaults to the
+    /// current directory. Ignored without `--recursive`.
+    #[arg(long = "scan-dir")]
+    pub scan_dir: Option<PathBuf>,
+
+    /// Report output format: human-readable text, machine-readable JSON, or
+    /// SARIF 2.1.0 for GitHub/GitLab code-scanning ingestion.
+    #[arg(long = "format", alias = "output", value_enum, default_value = "text")]
+    pub format: OutputFormat,
+
+    /// Which findings should cause a nonzero exit code: only confirmed
+    /// (known-CSV or tampered-integrity) findings by default, any finding
+    /// including publish-date heuristic matches, or never.
+    #[arg(long = "fail-on", value_enum, default_value = "confirmed")]
+    pub fail_on: FailOnSeverity,
+
+    /// Use the cached advisory list instead of fetching over the network.
+    /// Fails if no cached copy is available yet.
+    #[arg(long = "offline")]
+    pub offline: bool,
+
+    /// Maximum age, in seconds, of the cached advisory list before a fresh
+    /// download is attempted.
+    #[arg(long = "max-age", default_value_t = DEFAULT_MAX_AGE_SECS)]
+    pub max_age: u64,
+
+    /// Alternate URL to download the advisory CSV from, e.g. an internal
+    /// mirror of the Wiz IOC list.
+    #[arg(long = "advisory-url", default_value_t = AFFECTED_PACKAGES_URL.to_string())]
+    pub advisory_url: String,
+
+    /// Disable the on-disk publish-date cache: always query the registry for
+    /// every installed version's publish date, and don't write results back.
+    #[arg(long = "no-cache")]
+    pub no_cache: bool,
+
+    /// Treat every cached publish-date entry as stale, forcing a fresh
+    /// registry lookup for every installed version, but still refresh the
+    /// cache with the results. Ignored with `--no-cache`.
+    #[arg(long = "refresh-cache")]
+    pub refresh_cache: bool,
+
+    /// Maximum age, in seconds, of a cached publish-date entry before it's
+    /// considered stale and re-fetched.
+    #[arg(long = "cache-max-age", default_value_t = DEFAULT_PUBLISH_DATE_CACHE_MAX_AGE_SECS)]
+    pub cache_max_age: u64,
+
+    /// Local CSV file to use as the advisory list instead of downloading
+    /// one, e.g. an internal IOC source.
+    #[arg(long = "advisory-file")]
+    pub advisory_file: Option<PathBuf>,
 }
 
 /// Searches the current directory for npm lock files.
@@ -30,12 +105,12 @@ pub struct Args {
 /// # Supported lock files
 /// - package-lock.json
 /// - npm-shrinkwrap.json
+/// - yarn.lock
+/// - pnpm-lock.yaml
 fn find_npm_lock_file() -> Option<PathBuf> {
     let current_dir = env::current_dir().ok()?;
 
-    let lock_file_names = ["package-lock.json", "npm-shrinkwrap.json"];
-
-    for name in &lock_file_names {
+    for name in &LOCK_FILE_NAMES {
         let lock_file_path = current_dir.join(name);
         if lock_file_path.exists() && lock_file_path.is_file() {
             return Some(lock_file_path);
@@ -45,6 +120,55 @@ fn find_npm_lock_file() -> Option<PathBuf> {
     None
 }
 
+/// Recursively walks `root`, collecting every supported lock file found
+/// beneath it.
+///
+/// Descent skips `node_modules` (huge and never contains a lock file worth
+/// scanning on its own) and `.git` (VCS metadata) directories, and never
+/// follows symlinks, which rules out infinite loops from a symlink cycle.
+/// Entries are deduplicated by canonical path, so a lock file reachable via
+/// more than one path (e.g. a symlinked workspace package) is only scanned
+/// once.
+///
+/// # Returns
+/// A vector of paths to every lock file found, in the order they were
+/// discovered.
+pub fn find_all_lock_files(root: &Path) -> Vec<PathBuf> {
+    let mut seen_canonical = HashSet::new();
+    let mut found = Vec::new();
+
+    let walker = WalkDir::new(root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            !entry.file_type().is_dir()
+                || !SKIPPED_DIR_NAMES
+                    .iter()
+                    .any(|skipped| entry.file_name() == *skipped)
+        });
+
+    for entry in walker.flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let Some(name) = entry.file_name().to_str() else {
+            continue;
+        };
+        if !LOCK_FILE_NAMES.contains(&name) {
+            continue;
+        }
+
+        let path = entry.into_path();
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if seen_canonical.insert(canonical) {
+            found.push(path);
+        }
+    }
+
+    found
+}
+
 /// Resolves the lock file path from CLI arguments or auto-discovery.
 ///
 /// This function determines which package-lock.json file to use based on:
@@ -93,3 +217,62 @@ pub fn resolve_lock_file_path(args: &Args) -> PathBuf {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::find_all_lock_files;
+    use std::{fs, path::PathBuf, process};
+
+    /// A scratch directory under the OS temp dir, unique per test, removed
+    /// on drop so tests don't leak files or interfere with each other.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "shai_hulud_v2_checker_test_{}_{}",
+                name,
+                process::id()
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).expect("failed to create temp dir");
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn finds_lock_files_and_skips_node_modules_and_git() {
+        let dir = TempDir::new("finds_lock_files");
+        let root = &dir.0;
+
+        fs::write(root.join("package-lock.json"), "{}").unwrap();
+
+        let pkg_a = root.join("packages/a");
+        fs::create_dir_all(&pkg_a).unwrap();
+        fs::write(pkg_a.join("yarn.lock"), "").unwrap();
+
+        let nested_node_modules = root.join("node_modules/some-dep");
+        fs::create_dir_all(&nested_node_modules).unwrap();
+        fs::write(nested_node_modules.join("package-lock.json"), "{}").unwrap();
+
+        let git_dir = root.join(".git");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::write(git_dir.join("package-lock.json"), "{}").unwrap();
+
+        let mut found = find_all_lock_files(root);
+        found.sort();
+
+        assert_eq!(found.len(), 2);
+        assert!(found
+            .iter()
+            .any(|p| p.ends_with("package-lock.json")
+                && !p.to_string_lossy().contains("node_modules")));
+        assert!(found.iter().any(|p| p.ends_with("yarn.lock")));
+    }
+}
@@ -1,112 +1,413 @@
 //! Output and reporting functions for the vulnerability scanner.
 //!
-//! This module handles all console output and reporting, including:
-//! - Initial scan summary
-//! - Vulnerable packages reports
-//! - Possibly vulnerable packages reports
-//! - Skipped packages reports
-
-use crate::models::package::NpmLockPackages;
-
-/// Prints the initial scan summary with configuration and package counts.
-///
-/// # Arguments
-/// * `threads` - Number of concurrent threads configured for scanning
-/// * `package_count` - Total number of packages found in the lock file
-/// * `affected_count` - Total number of known affected packages in the vulnerability list
-pub fn print_scan_summary(threads: usize, package_count: usize, affected_count: usize) {
-    println!(
-        "🔧 Using {} concurrent threads for npm view commands",
-        threads
-    );
-    println!(
-        "🔄 Packages lock Json processed succesfully!\n\t🔎 Found {} installed packages",
-        package_count
-    );
-    println!(
-        "⏬ List of affected packages Downloaded! \n\t🔎 Found {} vulnerable 🦠 packages",
-        affected_count
-    );
+//! This module builds a structured `ScanReport` from one lock file's scan
+//! results and prints it as human-readable text, machine-readable JSON, or
+//! SARIF 2.1.0 (so GitHub/GitLab code-scanning can ingest it).
+
+use crate::models::package::{NpmLockPackages, PackageVulnerableRecord};
+use crate::network::AFFECTED_PACKAGES_URL;
+use crate::scanner::TamperedIntegrityFinding;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{collections::HashMap, path::Path};
+
+/// Supported report output formats, selected via `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
+pub enum OutputFormat {
+    /// Human-readable, emoji-decorated text (the original console output).
+    #[default]
+    Text,
+    /// Machine-readable JSON, for CI tooling and dashboards.
+    Json,
+    /// SARIF 2.1.0, for GitHub/GitLab code-scanning ingestion.
+    Sarif,
 }
 
-/// Prints a report of packages with known vulnerabilities.
-///
-/// # Arguments
-/// * `vulnerable_packages` - Collection of packages confirmed to be vulnerable
-pub fn print_vulnerable_packages_report(vulnerable_packages: &NpmLockPackages) {
-    let count = vulnerable_packages.packages.len();
+/// Which findings should cause the process to exit nonzero, selected via
+/// `--fail-on`. Lets a CI pipeline treat this as a build gate at whatever
+/// severity it cares about, without having to post-process the JSON output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
+pub enum FailOnSeverity {
+    /// Exit nonzero only on a confirmed finding (known-CSV match or
+    /// tampered-integrity mismatch).
+    #[default]
+    Confirmed,
+    /// Exit nonzero on any finding, including publish-date heuristic matches.
+    Any,
+    /// Never fail the process based on findings; useful when this tool is
+    /// only used for reporting, not gating.
+    None,
+}
 
-    if count == 0 {
-        println!("✅ No vulnerable packages found!");
-    } else {
-        println!("❗ Total vulnerable packages found: {}", count);
+/// How a finding was detected.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectionKind {
+    /// An installed version, or an installed integrity hash, matched an entry
+    /// in the known-vulnerable CSV list.
+    KnownVulnerable,
+    /// Not on the CSV list, but a version was published after the attack
+    /// date, which warrants manual review.
+    PublishDateHeuristic,
+    /// The installed tarball's integrity hash doesn't match what the lock
+    /// file recorded, regardless of whether the version string itself looks
+    /// vulnerable — direct evidence of a tampered/republished tarball.
+    TamperedIntegrity,
+}
 
-        for package_name in vulnerable_packages.packages.keys() {
-            println!("\t- {}", package_name);
-        }
+/// A supplementary integrity-hash signal surfaced alongside a finding's
+/// primary `detection`.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityAlert {
+    /// An installed integrity hash matches a known-malicious tarball hash,
+    /// regardless of what version string the lockfile records.
+    MaliciousHashMatch,
+    /// The version matched a known-vulnerable entry, but none of the
+    /// installed integrity hashes match a known-malicious one — possibly a
+    /// benign re-resolve to a different, unaffected build of that version.
+    VersionMatchHashMismatch,
+}
+
+/// A single vulnerability finding for one package.
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    /// The package name.
+    pub package: String,
+    /// Every installed version of the package found in the lock file. For
+    /// `TamperedIntegrity` findings, only the version whose integrity
+    /// mismatched.
+    pub installed_versions: Vec<String>,
+    /// The subset of `installed_versions` that matched a known-vulnerable
+    /// CSV entry. Empty for `PublishDateHeuristic` findings; for
+    /// `TamperedIntegrity` findings, the version whose integrity mismatched.
+    pub matched_versions: Vec<String>,
+    /// How this finding was detected: a known-CSV match, a tampered
+    /// integrity hash, or the publish-date heuristic.
+    pub detection: DetectionKind,
+    /// Any integrity-hash signal for this finding, if the advisory source
+    /// recorded known-malicious hashes for this package.
+    pub integrity_alert: Option<IntegrityAlert>,
+    /// The integrity hash the lock file recorded. Only set for
+    /// `TamperedIntegrity` findings.
+    pub recorded_integrity: Option<String>,
+    /// The integrity hash actually recomputed from the tarball (or reported
+    /// by the registry). Only set for `TamperedIntegrity` findings.
+    pub actual_integrity: Option<String>,
+    /// The advisory's version range that matched, e.g. `"=1.0.0||=1.0.1"`.
+    /// Only set for `KnownVulnerable` findings.
+    pub matched_range: Option<String>,
+}
+
+impl Finding {
+    /// Whether this finding is a confirmed (known-CSV or tampered-integrity)
+    /// vulnerability, as opposed to a publish-date heuristic match.
+    pub fn is_confirmed(&self) -> bool {
+        matches!(
+            self.detection,
+            DetectionKind::KnownVulnerable | DetectionKind::TamperedIntegrity
+        )
     }
 }
 
-/// Prints a report of packages that may be vulnerable based on publish dates.
-///
-/// # Arguments
-/// * `possibly_vulnerable_packages` - Collection of packages potentially vulnerable
-pub fn print_possibly_vulnerable_packages_report(possibly_vulnerable_packages: &NpmLockPackages) {
-    let count = possibly_vulnerable_packages.packages.len();
+/// A structured report of one lock file's scan results.
+#[derive(Debug, Serialize)]
+pub struct ScanReport {
+    /// Path to the lock file that was scanned.
+    pub lock_file_path: String,
+    /// URL of the advisory source the known-vulnerable list was fetched from,
+    /// or the local file path when it was read from `--advisory-file`.
+    pub advisory_source: String,
+    /// Every vulnerable or possibly-vulnerable package found.
+    pub findings: Vec<Finding>,
+    /// Packages whose publish-date check could not be completed (e.g. `npm
+    /// view` failed), so they weren't checked against the heuristic at all.
+    pub skipped_packages: Vec<String>,
+}
 
-    if count == 0 {
-        println!("✅ No possibly vulnerable packages found!");
-    } else {
-        println!("⚠️  Total possibly vulnerable packages found: {}", count);
+impl ScanReport {
+    /// Whether this report contains any confirmed (known-CSV or
+    /// tampered-integrity) vulnerability.
+    pub fn has_confirmed_vulnerability(&self) -> bool {
+        self.findings.iter().any(Finding::is_confirmed)
+    }
 
-        for package_name in possibly_vulnerable_packages.packages.keys() {
-            println!("\t- {}", package_name);
+    /// Whether this report should cause the process to exit nonzero, per the
+    /// requested `--fail-on` severity threshold.
+    pub fn exceeds_threshold(&self, threshold: FailOnSeverity) -> bool {
+        match threshold {
+            FailOnSeverity::None => false,
+            FailOnSeverity::Confirmed => self.has_confirmed_vulnerability(),
+            FailOnSeverity::Any => !self.findings.is_empty(),
         }
     }
 }
 
-/// Prints a report of packages that were skipped during vulnerability scanning.
-///
-/// # Arguments
-/// * `possibly_vulnerable_packages` - Collection to check for skipped packages
-pub fn print_skipped_packages_report(possibly_vulnerable_packages: &NpmLockPackages) {
-    let skipped_packages: Vec<_> = possibly_vulnerable_packages
+/// Builds a `ScanReport` from the outcome of a single lock file scan.
+pub fn build_scan_report(
+    lock_file_path: &Path,
+    affected_packages: &HashMap<String, PackageVulnerableRecord>,
+    vulnerable_packages: &NpmLockPackages,
+    possibly_vulnerable_packages: &NpmLockPackages,
+    tampered_integrity_findings: &[TamperedIntegrityFinding],
+) -> ScanReport {
+    let mut findings = Vec::new();
+
+    for (package, info) in &vulnerable_packages.packages {
+        let vuln_record = affected_packages.get(package);
+        let vuln_integrity = vuln_record.map(|r| r.integrity.as_slice()).unwrap_or(&[]);
+
+        let matched_versions: Vec<String> = info
+            .versions()
+            .filter(|installed| {
+                vuln_record.is_some_and(|record| record.version.matches(installed))
+            })
+            .map(String::from)
+            .collect();
+
+        let has_malicious_hash_match = !vuln_integrity.is_empty()
+            && info
+                .integrities()
+                .any(|installed| vuln_integrity.iter().any(|known| known == installed));
+
+        let integrity_alert = if has_malicious_hash_match {
+            Some(IntegrityAlert::MaliciousHashMatch)
+        } else if !matched_versions.is_empty() && !vuln_integrity.is_empty() {
+            Some(IntegrityAlert::VersionMatchHashMismatch)
+        } else {
+            None
+        };
+
+        findings.push(Finding {
+            package: package.clone(),
+            installed_versions: info.versions().map(String::from).collect(),
+            matched_versions,
+            detection: DetectionKind::KnownVulnerable,
+            integrity_alert,
+            recorded_integrity: None,
+            actual_integrity: None,
+            matched_range: vuln_record.map(|record| record.version.raw().to_string()),
+        });
+    }
+
+    for (package, info) in &possibly_vulnerable_packages.packages {
+        if info.skipped_scan {
+            continue;
+        }
+
+        findings.push(Finding {
+            package: package.clone(),
+            installed_versions: info.versions().map(String::from).collect(),
+            matched_versions: Vec::new(),
+            detection: DetectionKind::PublishDateHeuristic,
+            integrity_alert: None,
+            recorded_integrity: None,
+            actual_integrity: None,
+            matched_range: None,
+        });
+    }
+
+    for finding in tampered_integrity_findings {
+        findings.push(Finding {
+            package: finding.package.clone(),
+            installed_versions: vec![finding.version.clone()],
+            matched_versions: vec![finding.version.clone()],
+            detection: DetectionKind::TamperedIntegrity,
+            integrity_alert: None,
+            recorded_integrity: Some(finding.recorded_integrity.clone()),
+            actual_integrity: Some(finding.actual_integrity.clone()),
+            matched_range: None,
+        });
+    }
+
+    let skipped_packages = possibly_vulnerable_packages
         .packages
         .iter()
-        .filter(|(_, package_info)| package_info.skipped_scan)
+        .filter(|(_, info)| info.skipped_scan)
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    ScanReport {
+        lock_file_path: lock_file_path.to_string_lossy().to_string(),
+        advisory_source: AFFECTED_PACKAGES_URL.to_string(),
+        findings,
+        skipped_packages,
+    }
+}
+
+/// Prints `report` in the requested `format`.
+pub fn print_report(report: &ScanReport, format: OutputFormat) {
+    match format {
+        OutputFormat::Text => print_text_report(report),
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(report).expect("Failed to serialize report as JSON")
+            );
+        }
+        OutputFormat::Sarif => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&to_sarif(report))
+                    .expect("Failed to serialize report as SARIF")
+            );
+        }
+    }
+}
+
+/// Prints the original emoji-decorated, human-readable report.
+fn print_text_report(report: &ScanReport) {
+    let confirmed: Vec<_> = report
+        .findings
+        .iter()
+        .filter(|f| matches!(f.detection, DetectionKind::KnownVulnerable))
+        .collect();
+    let possible: Vec<_> = report
+        .findings
+        .iter()
+        .filter(|f| matches!(f.detection, DetectionKind::PublishDateHeuristic))
+        .collect();
+    let tampered: Vec<_> = report
+        .findings
+        .iter()
+        .filter(|f| matches!(f.detection, DetectionKind::TamperedIntegrity))
         .collect();
 
-    let count = skipped_packages.len();
+    println!("\n🔚 Scan completed!");
 
-    if count > 0 {
+    if confirmed.is_empty() {
+        println!("✅ No vulnerable packages found!");
+    } else {
+        println!("❗ Total vulnerable packages found: {}", confirmed.len());
+        for finding in &confirmed {
+            match finding.integrity_alert {
+                Some(IntegrityAlert::MaliciousHashMatch) => println!(
+                    "\t- {} (installed integrity matches a known-malicious tarball hash)",
+                    finding.package
+                ),
+                Some(IntegrityAlert::VersionMatchHashMismatch) => println!(
+                    "\t- {} (version matched, but installed integrity differs from the known-malicious hash \u{2014} possibly a benign re-resolve)",
+                    finding.package
+                ),
+                None => println!("\t- {}", finding.package),
+            }
+        }
+    }
+
+    if possible.is_empty() {
+        println!("✅ No possibly vulnerable packages found!");
+    } else {
         println!(
-            "⚠️  Total packages skipped during possible vulnerability check: {}",
-            count
+            "⚠️  Total possibly vulnerable packages found: {}",
+            possible.len()
+        );
+        for finding in &possible {
+            println!("\t- {}", finding.package);
+        }
+    }
+
+    if tampered.is_empty() {
+        println!("✅ No tampered package integrity found!");
+    } else {
+        println!(
+            "❗ Total packages with tampered integrity found: {}",
+            tampered.len()
         );
+        for finding in &tampered {
+            println!(
+                "\t- {}@{} (lock file says '{}', actual is '{}')",
+                finding.package,
+                finding.installed_versions.join(", "),
+                finding.recorded_integrity.as_deref().unwrap_or(""),
+                finding.actual_integrity.as_deref().unwrap_or(""),
+            );
+        }
+    }
 
-        for (package_name, _) in skipped_packages {
-            println!("\t- {}", package_name);
+    if !report.skipped_packages.is_empty() {
+        println!(
+            "⚠️  Total packages skipped during possible vulnerability check: {}",
+            report.skipped_packages.len()
+        );
+        for package in &report.skipped_packages {
+            println!("\t- {}", package);
         }
     }
 }
 
-/// Prints the complete final vulnerability report.
-///
-/// This orchestrates all report sections:
-/// 1. Known vulnerable packages
-/// 2. Possibly vulnerable packages
-/// 3. Skipped packages
-///
-/// # Arguments
-/// * `vulnerable_packages` - Collection of packages with known vulnerabilities
-/// * `possibly_vulnerable_packages` - Collection of packages potentially vulnerable
-pub fn print_final_report(
-    vulnerable_packages: &NpmLockPackages,
-    possibly_vulnerable_packages: &NpmLockPackages,
-) {
-    println!("\n🔚 Scan completed!");
+/// Converts a `ScanReport` into a minimal SARIF 2.1.0 log: one `rules` entry
+/// per finding (keyed by a package-derived rule id) and one matching
+/// `results` entry, so GitHub/GitLab code-scanning can group and surface
+/// them inline.
+fn to_sarif(report: &ScanReport) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = report
+        .findings
+        .iter()
+        .map(|finding| {
+            serde_json::json!({
+                "id": sarif_rule_id(&finding.package),
+                "shortDescription": {
+                    "text": format!("Shai Hulud V2: '{}' may be compromised", finding.package)
+                },
+            })
+        })
+        .collect();
+
+    let results: Vec<serde_json::Value> = report
+        .findings
+        .iter()
+        .map(|finding| {
+            let level = match finding.detection {
+                DetectionKind::KnownVulnerable | DetectionKind::TamperedIntegrity => "error",
+                DetectionKind::PublishDateHeuristic => "warning",
+            };
+
+            serde_json::json!({
+                "ruleId": sarif_rule_id(&finding.package),
+                "level": level,
+                "message": {
+                    "text": match finding.integrity_alert {
+                        Some(alert) => format!(
+                            "Package '{}' (installed: {}) matched as {:?}; integrity signal: {:?}",
+                            finding.package,
+                            finding.installed_versions.join(", "),
+                            finding.detection,
+                            alert,
+                        ),
+                        None => format!(
+                            "Package '{}' (installed: {}) matched as {:?}",
+                            finding.package,
+                            finding.installed_versions.join(", "),
+                            finding.detection,
+                        ),
+                    }
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": report.lock_file_path }
+                    }
+                }]
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "$schema": "https://raw.githubusercontent.com/oasis-tcs/sarif-spec/master/Schemata/sarif-schema-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "shai_hulud_v2_checker",
+                    "informationUri": report.advisory_source,
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
 
-    print_vulnerable_packages_report(vulnerable_packages);
-    print_possibly_vulnerable_packages_report(possibly_vulnerable_packages);
-    print_skipped_packages_report(possibly_vulnerable_packages);
+/// Derives a stable SARIF rule id from a package name.
+fn sarif_rule_id(package: &str) -> String {
+    format!("shai-hulud-v2/{}", package)
 }